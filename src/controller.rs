@@ -0,0 +1,249 @@
+use crate::game::Input;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Button {
+    Left,
+    Right,
+    SoftDrop,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Handling {
+    // Delayed Auto Shift: how long a direction is held before it repeats
+    pub das: Duration,
+    // Auto Repeat Rate: delay between repeated moves, zero slides to the wall
+    pub arr: Duration,
+    // soft drop speed as a multiple of gravity, zero drops to the floor
+    pub soft_drop_factor: u32,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Handling {
+            das: Duration::from_millis(167),
+            arr: Duration::from_millis(33),
+            soft_drop_factor: 20,
+        }
+    }
+}
+
+/*
+ * Turns held buttons into timed inputs, so movement speed no longer
+ * depends on the OS key repeat. Feed it presses and releases, then call
+ * `update` once per frame.
+ */
+pub struct Controller {
+    pub handling: Handling,
+    left: Option<Duration>,
+    right: Option<Duration>,
+    // most recently pressed direction wins while both are held
+    last_direction: Option<Button>,
+    soft_drop: Option<Duration>,
+    pending: Vec<Input>,
+}
+
+impl Controller {
+    pub fn new(handling: Handling) -> Controller {
+        Controller {
+            handling,
+            left: None,
+            right: None,
+            last_direction: None,
+            soft_drop: None,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn press(&mut self, button: Button) {
+        match button {
+            Button::Left => {
+                if self.left.is_none() {
+                    self.left = Some(Duration::from_millis(0));
+                    self.last_direction = Some(Button::Left);
+                    self.pending.push(Input::MoveLeft);
+                }
+            }
+            Button::Right => {
+                if self.right.is_none() {
+                    self.right = Some(Duration::from_millis(0));
+                    self.last_direction = Some(Button::Right);
+                    self.pending.push(Input::MoveRight);
+                }
+            }
+            Button::SoftDrop => {
+                if self.soft_drop.is_none() {
+                    self.soft_drop = Some(Duration::from_millis(0));
+                    self.pending.push(if self.handling.soft_drop_factor == 0 {
+                        Input::SoftDropToFloor
+                    } else {
+                        Input::SoftDrop
+                    });
+                }
+            }
+        }
+    }
+
+    pub fn release(&mut self, button: Button) {
+        match button {
+            Button::Left => {
+                self.left = None;
+                // releasing the direction that isn't moving the piece changes nothing else
+                if self.last_direction != Some(Button::Left) {
+                    return;
+                }
+                if self.right.is_some() {
+                    // the other direction takes over but has to charge again
+                    self.right = Some(Duration::from_millis(0));
+                    self.last_direction = Some(Button::Right);
+                } else {
+                    self.last_direction = None;
+                }
+            }
+            Button::Right => {
+                self.right = None;
+                if self.last_direction != Some(Button::Right) {
+                    return;
+                }
+                if self.left.is_some() {
+                    self.left = Some(Duration::from_millis(0));
+                    self.last_direction = Some(Button::Left);
+                } else {
+                    self.last_direction = None;
+                }
+            }
+            Button::SoftDrop => {
+                self.soft_drop = None;
+            }
+        }
+    }
+
+    // Forgets held buttons, e.g. when the window loses focus and key ups may be missed
+    pub fn release_all(&mut self) {
+        self.left = None;
+        self.right = None;
+        self.last_direction = None;
+        self.soft_drop = None;
+        self.pending.clear();
+    }
+
+    // Returns the inputs generated by presses and held buttons since the last call
+    pub fn update(&mut self, dt: Duration, gravity_delay: Duration) -> Vec<Input> {
+        let mut inputs = std::mem::take(&mut self.pending);
+        let handling = self.handling;
+
+        let direction = match self.last_direction {
+            Some(Button::Left) => Some((&mut self.left, Input::MoveLeft, Input::MoveLeftToWall)),
+            Some(Button::Right) => Some((&mut self.right, Input::MoveRight, Input::MoveRightToWall)),
+            _ => None,
+        };
+        if let Some((&mut Some(ref mut held_time), step, to_wall)) = direction {
+            let before = *held_time;
+            *held_time += dt;
+            if *held_time >= handling.das {
+                if handling.arr == Duration::from_millis(0) {
+                    inputs.push(to_wall);
+                } else {
+                    let repeats = repeat_count(*held_time, handling.das, handling.arr) -
+                        repeat_count(before, handling.das, handling.arr);
+                    for _ in 0..repeats {
+                        inputs.push(step);
+                    }
+                }
+            }
+        }
+
+        if let Some(ref mut held_time) = self.soft_drop {
+            if handling.soft_drop_factor == 0 {
+                inputs.push(Input::SoftDropToFloor);
+            } else {
+                let interval = gravity_delay / handling.soft_drop_factor;
+                let before = *held_time;
+                *held_time += dt;
+                let repeats = repeat_count(*held_time, interval, interval) -
+                    repeat_count(before, interval, interval);
+                for _ in 0..repeats {
+                    inputs.push(Input::SoftDrop);
+                }
+            }
+        }
+        inputs
+    }
+}
+
+// Number of repeats fired after being held for `held`: one at `delay`, then one every `rate`
+fn repeat_count(held: Duration, delay: Duration, rate: Duration) -> u128 {
+    if held < delay || rate == Duration::from_millis(0) {
+        0
+    } else {
+        (held - delay).as_nanos() / rate.as_nanos() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAVITY: Duration = Duration::from_millis(1000);
+
+    fn handling() -> Handling {
+        Handling {
+            das: Duration::from_millis(100),
+            arr: Duration::from_millis(20),
+            soft_drop_factor: 20,
+        }
+    }
+
+    fn moves(inputs: &[Input], input: Input) -> usize {
+        inputs.iter().filter(|held| **held == input).count()
+    }
+
+    #[test]
+    fn das_then_arr() {
+        let mut controller = Controller::new(handling());
+        controller.press(Button::Right);
+        let inputs = controller.update(Duration::from_millis(99), GRAVITY);
+        assert_eq!(inputs, vec![Input::MoveRight]);
+        let inputs = controller.update(Duration::from_millis(1), GRAVITY);
+        assert_eq!(inputs, vec![Input::MoveRight]);
+        let inputs = controller.update(Duration::from_millis(100), GRAVITY);
+        assert_eq!(moves(&inputs, Input::MoveRight), 5);
+    }
+
+    #[test]
+    fn zero_arr_slides_to_the_wall() {
+        let mut controller = Controller::new(Handling { arr: Duration::from_millis(0),
+                                                        ..handling() });
+        controller.press(Button::Left);
+        controller.update(Duration::from_millis(50), GRAVITY);
+        let inputs = controller.update(Duration::from_millis(50), GRAVITY);
+        assert_eq!(inputs, vec![Input::MoveLeftToWall]);
+    }
+
+    #[test]
+    fn releasing_the_active_direction_hands_over_with_a_fresh_das() {
+        let mut controller = Controller::new(handling());
+        controller.press(Button::Left);
+        controller.press(Button::Right);
+        controller.update(Duration::from_millis(200), GRAVITY);
+        controller.release(Button::Right);
+        let inputs = controller.update(Duration::from_millis(99), GRAVITY);
+        assert!(inputs.is_empty());
+        let inputs = controller.update(Duration::from_millis(1), GRAVITY);
+        assert_eq!(inputs, vec![Input::MoveLeft]);
+    }
+
+    #[test]
+    fn releasing_the_other_direction_keeps_the_repeat_going() {
+        let mut controller = Controller::new(handling());
+        controller.press(Button::Left);
+        controller.press(Button::Right);
+        let inputs = controller.update(Duration::from_millis(200), GRAVITY);
+        assert!(moves(&inputs, Input::MoveRight) > 1);
+        controller.release(Button::Left);
+        let inputs = controller.update(Duration::from_millis(100), GRAVITY);
+        assert_eq!(moves(&inputs, Input::MoveRight), 5);
+        controller.release(Button::Right);
+        assert!(controller.update(Duration::from_millis(100), GRAVITY).is_empty());
+    }
+}
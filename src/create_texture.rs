@@ -2,9 +2,8 @@ use sdl2::render::{Canvas, TextureCreator, Texture};
 use sdl2::video::{Window, WindowContext};
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
-use crate::tetris::Tetris;
+use tetris::tetris::Tetris;
 use crate::texture_group::TextureGroup;
-use crate::TETRIS_HEIGHT;
 
 pub fn create_texture_rect<'a>(
     canvas: &mut Canvas<Window>,
@@ -28,29 +27,54 @@ pub fn create_tetrimino_texture<'a>(
     canvas: &mut Canvas<Window>,
     texture_creator: &'a TextureCreator<WindowContext>,
     r: u8, g: u8, b: u8,
+    size: u32,
 ) -> TextureGroup<'a> {
     let mut tg = TextureGroup::new();
     tg.add(create_texture_rect(
         canvas,
         texture_creator,
         r, g, b,
-        TETRIS_HEIGHT,
-        TETRIS_HEIGHT,
+        size,
+        size,
     ).unwrap(), 0, 0);
     tg.add(create_texture_rect(
         canvas,
         texture_creator,
         (r as u16 * 2 / 4) as u8, (g as u16 * 2 / 4) as u8, (b as u16 * 2 / 4) as u8,
-        TETRIS_HEIGHT * 3/4,
-        TETRIS_HEIGHT * 3/4,
-    ).unwrap(), (TETRIS_HEIGHT / 8 + 1) as i32, (TETRIS_HEIGHT / 8 + 1) as i32);
+        size * 3/4,
+        size * 3/4,
+    ).unwrap(), (size / 8 + 1) as i32, (size / 8 + 1) as i32);
     tg.add(create_texture_rect(
         canvas,
         texture_creator,
         (r as u16 * 3 / 4) as u8, (g as u16 * 3 / 4) as u8, (b as u16 * 3 / 4) as u8,
-        TETRIS_HEIGHT * 3/4,
-        TETRIS_HEIGHT * 3/4,
-    ).unwrap(), (TETRIS_HEIGHT / 8) as i32, (TETRIS_HEIGHT / 8) as i32);
+        size * 3/4,
+        size * 3/4,
+    ).unwrap(), (size / 8) as i32, (size / 8) as i32);
+    tg
+}
+
+// The border of a cell, transparent inside
+pub fn create_outline_texture<'a>(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    r: u8, g: u8, b: u8,
+    size: u32,
+) -> TextureGroup<'a> {
+    let thickness = 3;
+    let mut tg = TextureGroup::new();
+    for (width, height, x, y) in [(size, thickness, 0, 0),
+                                  (size, thickness, 0, size - thickness),
+                                  (thickness, size, 0, 0),
+                                  (thickness, size, size - thickness, 0)].iter() {
+        tg.add(create_texture_rect(
+            canvas,
+            texture_creator,
+            r, g, b,
+            *width,
+            *height,
+        ).unwrap(), *x as i32, *y as i32);
+    }
     tg
 }
 
@@ -72,10 +96,57 @@ fn get_rect_from_text(text: &str, x: i32, y: i32) -> Option<Rect> {
     Some(Rect::new(x, y, text.len() as u32 * 20, 30))
 }
 
-pub fn display_game_information<'a>(
+pub fn display_text(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &sdl2::ttf::Font,
+    text: &str,
+    colour: Color,
+    x: i32, y: i32,
+) {
+    if text.is_empty() {
+        return;
+    }
+    let texture = create_texture_from_text(texture_creator, font,
+                                           text, colour.r, colour.g, colour.b)
+        .expect("Cannot render text");
+    canvas.copy(&texture, None, get_rect_from_text(text, x, y))
+        .expect("Couldn't copy text");
+}
+
+// A big title followed by one line per item, the selected one highlighted
+#[allow(clippy::too_many_arguments)]
+pub fn display_menu(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &sdl2::ttf::Font,
+    title: &str,
+    items: &[String],
+    selected: usize,
+    x: i32, y: i32,
+) {
+    let title_texture = create_texture_from_text(texture_creator, font,
+                                                 title, 255, 255, 255)
+        .expect("Cannot render text");
+    canvas.copy(&title_texture, None,
+                Rect::new(x, y, title.len() as u32 * 60, 90))
+        .expect("Couldn't copy text");
+    for (index, item) in items.iter().enumerate() {
+        let item_y = y + 150 + index as i32 * 45;
+        if index == selected {
+            display_text(canvas, texture_creator, font, &format!("> {}", item),
+                         Color::RGB(255, 220, 69), x, item_y);
+        } else {
+            display_text(canvas, texture_creator, font, &format!("  {}", item),
+                         Color::RGB(255, 255, 255), x, item_y);
+        }
+    }
+}
+
+pub fn display_game_information(
     tetris: &Tetris,
     canvas: &mut Canvas<Window>,
-    texture_creator: &'a TextureCreator<WindowContext>,
+    texture_creator: &TextureCreator<WindowContext>,
     font: &sdl2::ttf::Font,
     x: i32, y: i32,
 ) {
@@ -83,13 +154,13 @@ pub fn display_game_information<'a>(
     let lines_sent_text = format!("Lines sent: {}", tetris.nb_lines);
     let level_text = format!("Level: {}", tetris.current_level);
 
-    let score = create_texture_from_text(&texture_creator, &font,
+    let score = create_texture_from_text(texture_creator, font,
                                          &score_text, 255, 255, 255)
         .expect("Cannot render text");
-    let lines_sent = create_texture_from_text(&texture_creator, &font,
+    let lines_sent = create_texture_from_text(texture_creator, font,
                                               &lines_sent_text, 255, 255, 255)
         .expect("Cannot render text");
-    let level = create_texture_from_text(&texture_creator, &font,
+    let level = create_texture_from_text(texture_creator, font,
                                          &level_text, 255, 255, 255)
         .expect("Cannot render text");
 
@@ -102,4 +173,28 @@ pub fn display_game_information<'a>(
     canvas.copy(&level, None, get_rect_from_text(&score_text,
                                                  x, y + 70))
         .expect("Couldn't copy text");
+
+    // last clear, e.g. "B2B T-Spin Double" or "Combo 3"
+    let scoring = &tetris.scoring;
+    let mut clear_text = String::new();
+    if let Some(lock) = scoring.last_lock {
+        if let Some(name) = lock.name() {
+            if scoring.last_back_to_back {
+                clear_text.push_str("B2B ");
+            }
+            clear_text.push_str(&name);
+        }
+    }
+    if scoring.combo > 0 {
+        clear_text.push_str(&format!(" Combo {}", scoring.combo));
+    }
+    let clear_text = clear_text.trim();
+    if !clear_text.is_empty() {
+        let clear = create_texture_from_text(texture_creator, font,
+                                             clear_text, 255, 220, 69)
+            .expect("Cannot render text");
+        canvas.copy(&clear, None, get_rect_from_text(clear_text,
+                                                     x, y + 105))
+            .expect("Couldn't copy text");
+    }
 }
\ No newline at end of file
@@ -1,77 +1,183 @@
-use crate::tetris::Tetris;
-use std::time::SystemTime;
-use sdl2::event::Event;
+use tetris::{Button, Controller, Input, Session};
+use sdl2::controller::{Button as PadButton, GameController};
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
 
-pub fn handle_events(tetris: &mut Tetris, quit: &mut bool, timer: &mut SystemTime,
-                     event_pump: &mut sdl2::EventPump) -> bool {
-    let mut make_permanent = false;
-    let mut hold = false;
-    if let Some(ref mut piece) = tetris.current_piece {
-        let mut tmp_x = piece.x;
-        let mut tmp_y = piece.y;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+}
 
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } |
-                Event::KeyDown { keycode: Some(Keycode::Escape), .. } =>
-                    {
-                        *quit = true;
-                        break;
-                    }
-                Event::KeyDown { keycode: Some(Keycode::S), .. } |
-                Event::KeyDown { keycode: Some(Keycode::Down), .. } =>
-                    {
-                        *timer = SystemTime::now();
-                        tmp_y += 1;
-                    }
-                Event::KeyDown { keycode: Some(Keycode::D), .. } |
-                Event::KeyDown { keycode: Some(Keycode::Right), .. } =>
-                    {
-                        tmp_x += 1;
-                    }
-                Event::KeyDown { keycode: Some(Keycode::A), .. } |
-                Event::KeyDown { keycode: Some(Keycode::Left), .. } =>
-                    {
-                        tmp_x -= 1;
-                    }
-                Event::KeyDown { keycode: Some(Keycode::W), .. } |
-                Event::KeyDown { keycode: Some(Keycode::Up), .. } =>
-                    {
-                        piece.rotate(&tetris.game_map);
-                    }
-                Event::KeyDown { keycode: Some(Keycode::Space), .. } =>
-                    {
-                        hold = true;
-                    }
-                Event::KeyDown { keycode: Some(Keycode::Return), .. } =>
-                    {
-                        let x = piece.x;
-                        let mut y = piece.y;
-                        while piece.change_position(&tetris.game_map, x, y + 1)
-                            == true {
-                            y += 1;
-                        }
-                        make_permanent = true;
-                    }
-                _ => {}
-            }
-        }
-        if !make_permanent {
-            if piece.change_position(&tetris.game_map, tmp_x, tmp_y)
-                ==
-                false &&
-                tmp_y != piece.y {
-                make_permanent = true;
-            }
+// Everything a player can do in game, whatever it is bound to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GameKey {
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    Rotate,
+    RotateLeft,
+    Rotate180,
+    Hold,
+    Pause,
+    GiveUp,
+}
+
+fn keyboard_binding(keycode: Keycode) -> Option<GameKey> {
+    match keycode {
+        Keycode::A | Keycode::Left => Some(GameKey::Left),
+        Keycode::D | Keycode::Right => Some(GameKey::Right),
+        Keycode::S | Keycode::Down => Some(GameKey::SoftDrop),
+        Keycode::Return => Some(GameKey::HardDrop),
+        Keycode::W | Keycode::Up | Keycode::X => Some(GameKey::Rotate),
+        Keycode::Q | Keycode::Z | Keycode::LCtrl => Some(GameKey::RotateLeft),
+        Keycode::E | Keycode::C => Some(GameKey::Rotate180),
+        Keycode::Space => Some(GameKey::Hold),
+        Keycode::P | Keycode::F1 => Some(GameKey::Pause),
+        Keycode::Escape => Some(GameKey::GiveUp),
+        _ => None,
+    }
+}
+
+// Keyboard split in two for versus: left hand for player 1, right hand for player 2
+fn versus_keyboard_binding(keycode: Keycode) -> Option<(usize, GameKey)> {
+    match keycode {
+        Keycode::A => Some((0, GameKey::Left)),
+        Keycode::D => Some((0, GameKey::Right)),
+        Keycode::S => Some((0, GameKey::SoftDrop)),
+        Keycode::Space => Some((0, GameKey::HardDrop)),
+        Keycode::W => Some((0, GameKey::Rotate)),
+        Keycode::Q => Some((0, GameKey::RotateLeft)),
+        Keycode::E => Some((0, GameKey::Rotate180)),
+        Keycode::LShift => Some((0, GameKey::Hold)),
+        Keycode::Left => Some((1, GameKey::Left)),
+        Keycode::Right => Some((1, GameKey::Right)),
+        Keycode::Down => Some((1, GameKey::SoftDrop)),
+        Keycode::Return => Some((1, GameKey::HardDrop)),
+        Keycode::Up => Some((1, GameKey::Rotate)),
+        Keycode::RCtrl => Some((1, GameKey::RotateLeft)),
+        Keycode::Slash => Some((1, GameKey::Rotate180)),
+        Keycode::RShift => Some((1, GameKey::Hold)),
+        // pausing and giving up are for everyone
+        _ => keyboard_binding(keycode)
+            .filter(|key| *key == GameKey::Pause || *key == GameKey::GiveUp)
+            .map(|key| (0, key)),
+    }
+}
+
+fn controller_binding(button: PadButton) -> Option<GameKey> {
+    match button {
+        PadButton::DPadLeft => Some(GameKey::Left),
+        PadButton::DPadRight => Some(GameKey::Right),
+        PadButton::DPadDown => Some(GameKey::SoftDrop),
+        PadButton::DPadUp => Some(GameKey::HardDrop),
+        PadButton::A => Some(GameKey::Rotate),
+        PadButton::B => Some(GameKey::RotateLeft),
+        PadButton::Y => Some(GameKey::Rotate180),
+        PadButton::X | PadButton::LeftShoulder | PadButton::RightShoulder => Some(GameKey::Hold),
+        PadButton::Start => Some(GameKey::Pause),
+        PadButton::Back => Some(GameKey::GiveUp),
+        _ => None,
+    }
+}
+
+// The player, the bound key and whether it was pressed (true) or released (false)
+fn game_key(event: &Event, players: usize, pads: &[GameController])
+            -> Option<(usize, GameKey, bool)> {
+    let keyboard = |keycode: Keycode| if players > 1 {
+        versus_keyboard_binding(keycode)
+    } else {
+        keyboard_binding(keycode).map(|key| (0, key))
+    };
+    // each pad plays for the player matching the order it was plugged in
+    let pad_player = |which: u32| pads.iter()
+        .position(|pad| pad.instance_id() == which)
+        .unwrap_or(0)
+        .min(players - 1);
+    match *event {
+        Event::KeyDown { keycode: Some(keycode), repeat: false, .. } =>
+            keyboard(keycode).map(|(player, key)| (player, key, true)),
+        Event::KeyUp { keycode: Some(keycode), .. } =>
+            keyboard(keycode).map(|(player, key)| (player, key, false)),
+        Event::ControllerButtonDown { which, button, .. } =>
+            controller_binding(button).map(|key| (pad_player(which), key, true)),
+        Event::ControllerButtonUp { which, button, .. } =>
+            controller_binding(button).map(|key| (pad_player(which), key, false)),
+        _ => None,
+    }
+}
+
+pub fn menu_action(event: &Event) -> Option<MenuAction> {
+    match *event {
+        Event::KeyDown { keycode: Some(keycode), .. } => match keycode {
+            Keycode::Up | Keycode::W => Some(MenuAction::Up),
+            Keycode::Down | Keycode::S => Some(MenuAction::Down),
+            Keycode::Left | Keycode::A => Some(MenuAction::Left),
+            Keycode::Right | Keycode::D => Some(MenuAction::Right),
+            Keycode::Return | Keycode::Space => Some(MenuAction::Confirm),
+            Keycode::Escape | Keycode::Backspace => Some(MenuAction::Back),
+            _ => None,
+        },
+        Event::ControllerButtonDown { button, .. } => match button {
+            PadButton::DPadUp => Some(MenuAction::Up),
+            PadButton::DPadDown => Some(MenuAction::Down),
+            PadButton::DPadLeft => Some(MenuAction::Left),
+            PadButton::DPadRight => Some(MenuAction::Right),
+            PadButton::A | PadButton::Start => Some(MenuAction::Confirm),
+            PadButton::B | PadButton::Back => Some(MenuAction::Back),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// Routes `event` to the controller or inputs of the player it belongs to
+pub fn handle_game_event(event: &Event, pads: &[GameController], give_up: &mut bool,
+                         session: &mut Session, controllers: &mut [Controller],
+                         inputs: &mut [Vec<Input>]) {
+    if let Event::Window { win_event: WindowEvent::FocusLost, .. } |
+           Event::Window { win_event: WindowEvent::Minimized, .. } = *event {
+        session.pause();
+        for controller in controllers.iter_mut() {
+            controller.release_all();
         }
+        return;
     }
-    if make_permanent {
-        tetris.make_permanent();
-        *timer = SystemTime::now();
+    let (player, key, pressed) = match game_key(event, controllers.len(), pads) {
+        Some(key) => key,
+        None => return,
+    };
+    let controller = &mut controllers[player];
+    if !pressed {
+        match key {
+            GameKey::Left => controller.release(Button::Left),
+            GameKey::Right => controller.release(Button::Right),
+            GameKey::SoftDrop => controller.release(Button::SoftDrop),
+            _ => {}
+        }
+        return;
     }
-    if hold {
-        tetris.hold_piece();
+    let inputs = &mut inputs[player];
+    match key {
+        GameKey::Left => controller.press(Button::Left),
+        GameKey::Right => controller.press(Button::Right),
+        GameKey::SoftDrop => controller.press(Button::SoftDrop),
+        GameKey::HardDrop => inputs.push(Input::HardDrop),
+        GameKey::Rotate => inputs.push(Input::Rotate),
+        GameKey::RotateLeft => inputs.push(Input::RotateLeft),
+        GameKey::Rotate180 => inputs.push(Input::Rotate180),
+        GameKey::Hold => inputs.push(Input::Hold),
+        GameKey::Pause => {
+            session.toggle_pause();
+            for controller in controllers.iter_mut() {
+                controller.release_all();
+            }
+        }
+        GameKey::GiveUp => *give_up = true,
     }
-    make_permanent
-}
\ No newline at end of file
+}
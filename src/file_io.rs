@@ -1,7 +1,7 @@
 use std::fs::File;
 use std::io::{self, Read, Write};
 
-const HIGHSCORE_FILE: &'static str = "scores.txt";
+const HIGHSCORE_FILE: &str = "scores.txt";
 
 fn write_into_file(content: &str, file_name: &str) -> io::Result<()> {
     let mut f = File::create(file_name)?;
@@ -24,7 +24,7 @@ pub fn save_highscores_and_lines(highscores: &[u32],
                              number_of_lines: &[u32]) -> bool {
     let s_highscores = slice_to_string(highscores);
     let s_number_of_lines = slice_to_string(number_of_lines);
-    write_into_file(&*format!("{}\n{}\n", s_highscores, s_number_of_lines), HIGHSCORE_FILE).is_ok()
+    write_into_file(&format!("{}\n{}\n", s_highscores, s_number_of_lines), HIGHSCORE_FILE).is_ok()
 }
 
 fn line_to_slice(line: &str) -> Vec<u32> {
@@ -33,8 +33,7 @@ fn line_to_slice(line: &str) -> Vec<u32> {
 
 pub fn load_highscores_and_lines() -> Option<(Vec<u32>, Vec<u32>)> {
     if let Ok(content) = read_from_file(HIGHSCORE_FILE) {
-        let mut lines = content.splitn(2, "\n").map(|line|
-            line_to_slice(line)).collect::<Vec<_>>();
+        let mut lines = content.splitn(2, "\n").map(line_to_slice).collect::<Vec<_>>();
         if lines.len() == 2 {
             let (number_lines, highscores) = (lines.pop().unwrap(),
                                               lines.pop().unwrap());
@@ -42,4 +41,18 @@ pub fn load_highscores_and_lines() -> Option<(Vec<u32>, Vec<u32>)> {
         }
     }
     None
-}
\ No newline at end of file
+}
+
+// File holding the ranking of the mode called `name`
+pub fn ranking_file(name: &str) -> String {
+    format!("{}.txt", name.to_lowercase())
+}
+
+// One ranking per file, best first
+pub fn save_ranking(values: &[u32], file_name: &str) -> bool {
+    write_into_file(&format!("{}\n", slice_to_string(values)), file_name).is_ok()
+}
+
+pub fn load_ranking(file_name: &str) -> Option<Vec<u32>> {
+    read_from_file(file_name).ok().map(|content| line_to_slice(content.trim()))
+}
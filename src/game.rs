@@ -0,0 +1,606 @@
+use crate::tetris::{Goal, Tetris, DEFAULT_HEIGHT, DEFAULT_NEXT_COUNT, DEFAULT_WIDTH,
+                    INSTANT_GRAVITY};
+use crate::garbage::{self, GarbageGenerator, GarbageQueue};
+use crate::mode::{GameMode, TopOutPolicy, DIG_ROWS, SPRINT_LINES};
+use crate::randomizer::RandomizerKind;
+use crate::scoring::{HARD_DROP_POINTS, SOFT_DROP_POINTS};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Input {
+    MoveLeft,
+    MoveRight,
+    MoveLeftToWall,
+    MoveRightToWall,
+    SoftDrop,
+    SoftDropToFloor,
+    HardDrop,
+    Rotate,
+    RotateLeft,
+    Rotate180,
+    Hold,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig {
+    pub mode: GameMode,
+    pub randomizer: RandomizerKind,
+    // playfield size in cells, not counting the hidden buffer
+    pub width: usize,
+    pub height: usize,
+    // length of the next queue preview, from 1 to 7
+    pub next_count: usize,
+    // time a grounded piece waits before locking, unless the level has its own
+    pub lock_delay: Duration,
+    // successful moves/rotations on the ground that restart the lock delay
+    pub max_lock_resets: u32,
+    // length of an Ultra game
+    pub time_limit: Duration,
+    // garbage rows to clear in Dig
+    pub dig_lines: u32,
+    // how long locked cells stay visible in Fading
+    pub fade_time: Duration,
+    // time received garbage waits before it can rise in versus
+    pub garbage_delay: Duration,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig {
+            mode: GameMode::Marathon,
+            randomizer: RandomizerKind::SevenBag,
+            width: DEFAULT_WIDTH,
+            height: DEFAULT_HEIGHT,
+            next_count: DEFAULT_NEXT_COUNT,
+            lock_delay: Duration::from_millis(500),
+            max_lock_resets: 15,
+            time_limit: Duration::from_secs(120),
+            dig_lines: 10,
+            fade_time: Duration::from_secs(5),
+            garbage_delay: Duration::from_millis(500),
+        }
+    }
+}
+
+impl GameConfig {
+    pub fn goal(&self) -> Goal {
+        match self.mode {
+            GameMode::Sprint => Goal::Lines(SPRINT_LINES),
+            GameMode::Ultra => Goal::Time(self.time_limit),
+            GameMode::Dig => Goal::Garbage(self.dig_lines),
+            _ => Goal::None,
+        }
+    }
+
+    // How long locked cells stay visible, None if they always do
+    pub fn stack_fade(&self) -> Option<Duration> {
+        match self.mode {
+            GameMode::Fading => Some(self.fade_time),
+            GameMode::Invisible => Some(Duration::from_millis(0)),
+            _ => None,
+        }
+    }
+}
+
+/*
+ * Owns the rules that used to live in the frontend loop:
+ * gravity, locking, spawning and game over detection.
+ */
+pub struct Game {
+    pub tetris: Tetris,
+    pub config: GameConfig,
+    pub seed: u64,
+    pub over: bool,
+    // gravity and lock delay are frozen while paused
+    pub paused: bool,
+    // the mode's goal was reached, as opposed to topping out
+    pub completed: bool,
+    // time spent playing, pauses excluded
+    pub elapsed: Duration,
+    // pieces locked so far
+    pub pieces: u32,
+    // top outs the mode recovered from instead of ending the game
+    pub recoveries: u32,
+    // garbage lines sent by this game and not yet handed to the opponents
+    pub attack: u32,
+    pub lines_sent: u32,
+    // garbage sent by the opponents that hasn't risen yet
+    pub incoming: GarbageQueue,
+    garbage: GarbageGenerator,
+    gravity_timer: Duration,
+    // time left before the next piece spawns
+    are_timer: Duration,
+    lock_timer: Duration,
+    lock_resets: u32,
+    lowest_y: isize,
+}
+
+impl Game {
+    // The seed fully determines the piece sequence
+    pub fn new(seed: u64) -> Game {
+        Game::with_config(GameConfig::default(), seed)
+    }
+
+    pub fn with_config(config: GameConfig, seed: u64) -> Game {
+        // big minos need a board made of whole big cells, at least 4 minos wide
+        // for the I to spawn
+        let scale = config.mode.scale();
+        let width = config.width.max(4 * scale).next_multiple_of(scale);
+        let height = config.height.next_multiple_of(scale);
+        let mut tetris = Tetris::with_randomizer(config.randomizer.create(seed),
+                                                 width, height, config.next_count);
+        tetris.scale = scale;
+        tetris.level_up = config.mode.levels_up();
+        tetris.curve = config.mode.level_curve();
+        tetris.goal = config.goal();
+        let mut game = Game {
+            tetris,
+            config,
+            seed,
+            over: false,
+            paused: false,
+            completed: false,
+            elapsed: Duration::from_millis(0),
+            pieces: 0,
+            recoveries: 0,
+            attack: 0,
+            lines_sent: 0,
+            incoming: GarbageQueue::new(config.garbage_delay),
+            garbage: GarbageGenerator::new(seed, width),
+            gravity_timer: Duration::from_millis(0),
+            are_timer: Duration::from_millis(0),
+            lock_timer: Duration::from_millis(0),
+            lock_resets: 0,
+            lowest_y: 0,
+        };
+        game.refill_garbage();
+        game
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0. {
+            self.pieces as f64 / seconds
+        } else {
+            0.
+        }
+    }
+
+    // Advances the game by `dt`, applying `inputs` in order first
+    pub fn step(&mut self, inputs: &[Input], dt: Duration) {
+        if self.over || self.paused {
+            return;
+        }
+        self.elapsed += dt;
+        self.tetris.clock = self.elapsed;
+        self.check_goal();
+        if self.over {
+            return;
+        }
+        self.are_timer = self.are_timer.saturating_sub(dt);
+        self.incoming.update(dt);
+        self.spawn_if_needed();
+        for input in inputs {
+            if self.over {
+                return;
+            }
+            self.handle_input(*input);
+            self.apply_instant_gravity();
+        }
+        self.apply_gravity(dt);
+        self.update_lock_delay(dt);
+        self.spawn_if_needed();
+    }
+
+    fn spawn_if_needed(&mut self) {
+        if self.over || self.tetris.current_piece.is_some() ||
+            self.are_timer > Duration::from_millis(0) {
+            return;
+        }
+        if self.tetris.spawn_next_piece() {
+            self.reset_piece_state();
+            self.apply_instant_gravity();
+        }
+        self.check_top_out();
+    }
+
+    fn check_top_out(&mut self) {
+        if self.tetris.top_out.is_none() {
+            return;
+        }
+        match self.config.mode.top_out_policy() {
+            TopOutPolicy::EndGame => self.over = true,
+            TopOutPolicy::ClearBoard => {
+                self.tetris.clear_board();
+                self.recoveries += 1;
+            }
+        }
+    }
+
+    fn reset_piece_state(&mut self) {
+        self.gravity_timer = Duration::from_millis(0);
+        self.lock_timer = Duration::from_millis(0);
+        self.lock_resets = 0;
+        if let Some(ref piece) = self.tetris.current_piece {
+            self.lowest_y = piece.y;
+        }
+    }
+
+    fn handle_input(&mut self, input: Input) {
+        let grounded = self.is_grounded();
+        let moved = match input {
+            Input::MoveLeft => self.shift(-1),
+            Input::MoveRight => self.shift(1),
+            Input::MoveLeftToWall | Input::MoveRightToWall => {
+                let dx = if input == Input::MoveLeftToWall { -1 } else { 1 };
+                let mut moved = false;
+                while self.shift(dx) {
+                    moved = true;
+                }
+                moved
+            }
+            Input::SoftDrop => {
+                self.gravity_timer = Duration::from_millis(0);
+                if self.move_down() {
+                    self.tetris.update_score(SOFT_DROP_POINTS);
+                }
+                false
+            }
+            Input::SoftDropToFloor => {
+                self.gravity_timer = Duration::from_millis(0);
+                while self.move_down() {
+                    self.tetris.update_score(SOFT_DROP_POINTS);
+                }
+                false
+            }
+            Input::HardDrop => {
+                while self.move_down() {
+                    self.tetris.update_score(HARD_DROP_POINTS);
+                }
+                self.lock();
+                false
+            }
+            Input::Rotate | Input::RotateLeft | Input::Rotate180 => {
+                let game_map = &self.tetris.game_map;
+                match self.tetris.current_piece {
+                    Some(ref mut piece) => match input {
+                        Input::RotateLeft => piece.rotate_left(game_map),
+                        Input::Rotate180 => piece.rotate_180(game_map),
+                        _ => piece.rotate(game_map),
+                    },
+                    None => false,
+                }
+            }
+            Input::Hold => {
+                if self.tetris.hold_piece() {
+                    self.reset_piece_state();
+                    self.check_top_out();
+                }
+                false
+            }
+        };
+        if moved && grounded && self.lock_resets < self.config.max_lock_resets {
+            self.lock_timer = Duration::from_millis(0);
+            self.lock_resets += 1;
+        }
+    }
+
+    fn shift(&mut self, dx: isize) -> bool {
+        let game_map = &self.tetris.game_map;
+        match self.tetris.current_piece {
+            Some(ref mut piece) => {
+                let (x, y) = (piece.x + dx, piece.y);
+                piece.change_position(game_map, x, y)
+            }
+            None => false,
+        }
+    }
+
+    fn move_down(&mut self) -> bool {
+        let game_map = &self.tetris.game_map;
+        match self.tetris.current_piece {
+            Some(ref mut piece) => {
+                let (x, y) = (piece.x, piece.y + 1);
+                piece.change_position(game_map, x, y)
+            }
+            None => false,
+        }
+    }
+
+    fn is_grounded(&self) -> bool {
+        match self.tetris.current_piece {
+            Some(ref piece) => !piece.test_position(&self.tetris.game_map,
+                                                    piece.current_state as usize,
+                                                    piece.x, piece.y + 1),
+            None => false,
+        }
+    }
+
+    // Ends the game as a win, e.g. when every opponent topped out
+    pub fn finish(&mut self) {
+        self.completed = true;
+        self.over = true;
+    }
+
+    // Garbage lines sent since the last call
+    pub fn take_attack(&mut self) -> u32 {
+        std::mem::take(&mut self.attack)
+    }
+
+    // Queues garbage sent by an opponent, it rises after the configured delay
+    pub fn receive_garbage(&mut self, lines: u32) {
+        if !self.over {
+            self.incoming.push(lines);
+        }
+    }
+
+    // Pushes the garbage whose delay is over in from the bottom of the board
+    fn raise_garbage(&mut self) {
+        for lines in self.incoming.take_ready() {
+            let lines = self.garbage.chunk(lines as usize);
+            self.tetris.push_lines(lines);
+        }
+    }
+
+    fn lock(&mut self) {
+        if self.tetris.current_piece.is_some() {
+            let result = self.tetris.make_permanent();
+            // attacks cancel incoming garbage first, only the rest is sent
+            let attack = garbage::attack(&result, &self.tetris.scoring);
+            let sent = self.incoming.cancel(attack);
+            self.attack += sent;
+            self.lines_sent += sent;
+            // clearing lines holds the garbage back until the next lock
+            if result.lines == 0 {
+                self.raise_garbage();
+            }
+            self.pieces += 1;
+            self.gravity_timer = Duration::from_millis(0);
+            self.are_timer = self.tetris.level().are;
+            self.check_top_out();
+            self.check_goal();
+            self.refill_garbage();
+        }
+    }
+
+    // Keeps the board of a Dig game filled with garbage until the last rows to dig
+    fn refill_garbage(&mut self) {
+        let goal = match self.tetris.goal {
+            Goal::Garbage(lines) => lines,
+            _ => return,
+        };
+        if self.over {
+            return;
+        }
+        // room is left at the top on small boards, but there is always a row to dig
+        let rows = DIG_ROWS.min(self.tetris.height.saturating_sub(4)).max(1);
+        let left = goal.saturating_sub(self.tetris.garbage_cleared) as usize;
+        let wanted = rows.min(left);
+        let present = self.tetris.garbage_height();
+        if present < wanted {
+            let lines = self.garbage.lines(wanted - present);
+            self.tetris.push_lines(lines);
+            self.check_top_out();
+        }
+    }
+
+    fn check_goal(&mut self) {
+        if !self.over && self.tetris.goal_reached(self.elapsed) {
+            if let Goal::Time(limit) = self.tetris.goal {
+                self.elapsed = limit;
+            }
+            self.completed = true;
+            self.over = true;
+        }
+    }
+
+    fn apply_gravity(&mut self, dt: Duration) {
+        if self.tetris.current_piece.is_none() {
+            return;
+        }
+        self.gravity_timer += dt;
+        // above 1G the piece falls several rows in one step
+        let delay = self.tetris.gravity_delay();
+        while self.gravity_timer > delay {
+            self.gravity_timer -= delay;
+            if !self.move_down() {
+                self.gravity_timer = Duration::from_millis(0);
+            }
+        }
+    }
+
+    // At 20G pieces never stay in the air, not even between two inputs
+    fn apply_instant_gravity(&mut self) {
+        if self.tetris.level().gravity >= INSTANT_GRAVITY {
+            while self.move_down() {}
+        }
+    }
+
+    fn update_lock_delay(&mut self, dt: Duration) {
+        let y = match self.tetris.current_piece {
+            Some(ref piece) => piece.y,
+            None => return,
+        };
+        if y > self.lowest_y {
+            // reaching a new lowest row gives the piece a fresh set of resets
+            self.lowest_y = y;
+            self.lock_resets = 0;
+            self.lock_timer = Duration::from_millis(0);
+        }
+        if !self.is_grounded() {
+            // once the resets are used up, leaving the ground no longer restarts the delay
+            if self.lock_resets < self.config.max_lock_resets {
+                self.lock_timer = Duration::from_millis(0);
+            }
+            return;
+        }
+        self.lock_timer += dt;
+        let lock_delay = self.tetris.level().lock_delay.unwrap_or(self.config.lock_delay);
+        if self.lock_timer >= lock_delay {
+            self.lock();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetrimino::TetriminoKind;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    // A started game whose first piece is a T
+    fn t_game(config: GameConfig) -> Game {
+        (0..)
+            .map(|seed| {
+                let mut game = Game::with_config(config, seed);
+                game.step(&[], FRAME);
+                game
+            })
+            .find(|game| game.tetris.current_piece.as_ref()
+                .is_some_and(|piece| piece.kind == TetriminoKind::T))
+            .unwrap()
+    }
+
+    // Cells of the pieces locked so far
+    fn locked_cells(game: &Game) -> usize {
+        game.tetris.game_map.iter().flatten().filter(|case| **case != 0).count()
+    }
+
+    fn drop_to_floor(game: &mut Game) {
+        game.step(&[Input::SoftDrop; 40], FRAME);
+    }
+
+    #[test]
+    fn grounded_piece_locks_after_the_lock_delay() {
+        let mut game = t_game(GameConfig::default());
+        drop_to_floor(&mut game);
+        game.step(&[], Duration::from_millis(400));
+        assert_eq!(locked_cells(&game), 0);
+        game.step(&[], Duration::from_millis(100));
+        assert_eq!(locked_cells(&game), 4);
+    }
+
+    #[test]
+    fn moves_on_the_ground_restart_the_lock_delay() {
+        let mut game = t_game(GameConfig::default());
+        drop_to_floor(&mut game);
+        for _ in 0..5 {
+            game.step(&[Input::MoveLeft], Duration::from_millis(400));
+            game.step(&[Input::MoveRight], Duration::from_millis(400));
+        }
+        assert_eq!(locked_cells(&game), 0);
+    }
+
+    #[test]
+    fn lock_resets_are_capped() {
+        let config = GameConfig { max_lock_resets: 4, ..GameConfig::default() };
+        let mut game = t_game(config);
+        drop_to_floor(&mut game);
+        let mut steps = 0;
+        while locked_cells(&game) == 0 && steps < 100 {
+            // turning back to the spawn state lifts the piece off the floor
+            let input = if steps % 2 == 0 { Input::Rotate } else { Input::RotateLeft };
+            game.step(&[input], Duration::from_millis(100));
+            steps += 1;
+        }
+        // 4 resets, then 5 grounded steps of 100 ms out of every 2
+        assert!(steps <= 2 * (4 + 5) + 2, "locked after {} steps", steps);
+    }
+
+    #[test]
+    fn hard_drop_locks_at_once() {
+        let mut game = t_game(GameConfig::default());
+        game.step(&[Input::HardDrop], FRAME);
+        assert_eq!(locked_cells(&game), 4);
+        assert!(game.tetris.score > 0);
+    }
+
+    #[test]
+    fn paused_games_stand_still() {
+        let mut game = t_game(GameConfig::default());
+        let y = game.tetris.current_piece.as_ref().unwrap().y;
+        game.pause();
+        game.step(&[Input::HardDrop], Duration::from_secs(10));
+        assert_eq!(locked_cells(&game), 0);
+        assert_eq!(game.tetris.current_piece.as_ref().unwrap().y, y);
+    }
+
+    #[test]
+    fn sprint_ends_when_the_goal_is_reached() {
+        let config = GameConfig { mode: GameMode::Sprint, ..GameConfig::default() };
+        let mut game = Game::with_config(config, 1);
+        game.tetris.nb_lines = SPRINT_LINES;
+        game.step(&[Input::HardDrop], FRAME);
+        assert!(game.over && game.completed);
+    }
+
+    #[test]
+    fn ultra_time_is_clamped_to_the_limit() {
+        let config = GameConfig { mode: GameMode::Ultra, ..GameConfig::default() };
+        let mut game = Game::with_config(config, 1);
+        game.step(&[], config.time_limit + Duration::from_secs(1));
+        assert!(game.over && game.completed);
+        assert_eq!(game.elapsed, config.time_limit);
+    }
+
+    #[test]
+    fn dig_starts_with_garbage_rows() {
+        let config = GameConfig { mode: GameMode::Dig, ..GameConfig::default() };
+        let game = Game::with_config(config, 1);
+        assert_eq!(game.tetris.garbage_height(), DIG_ROWS);
+        let config = GameConfig { dig_lines: 3, ..config };
+        let game = Game::with_config(config, 1);
+        assert_eq!(game.tetris.garbage_height(), 3);
+    }
+
+    #[test]
+    fn dig_keeps_a_garbage_row_on_small_boards() {
+        let config = GameConfig { mode: GameMode::Dig, height: 4, ..GameConfig::default() };
+        let game = Game::with_config(config, 1);
+        assert_eq!(game.tetris.garbage_height(), 1);
+    }
+
+    #[test]
+    fn big_pieces_all_spawn_on_the_narrowest_board() {
+        let config = GameConfig { mode: GameMode::Big, width: 4, ..GameConfig::default() };
+        let mut spawned = Vec::new();
+        for seed in 0..50 {
+            let mut game = Game::with_config(config, seed);
+            game.step(&[], FRAME);
+            assert_eq!(game.tetris.width, 8);
+            assert!(!game.over, "seed {} blocked out", seed);
+            spawned.push(game.tetris.current_piece.unwrap().kind);
+        }
+        for kind in TetriminoKind::ALL.iter() {
+            assert!(spawned.contains(kind), "no {:?} in 50 seeds", kind);
+        }
+    }
+
+    #[test]
+    fn received_garbage_waits_for_its_delay() {
+        let config = GameConfig { mode: GameMode::Versus, ..GameConfig::default() };
+        let mut game = Game::with_config(config, 7);
+        game.step(&[], FRAME);
+        game.receive_garbage(3);
+        game.step(&[Input::HardDrop], FRAME);
+        assert_eq!(game.tetris.garbage_height(), 0);
+        assert_eq!(game.incoming.total(), 3);
+        game.step(&[], config.garbage_delay);
+        game.step(&[Input::HardDrop], FRAME);
+        assert_eq!(game.tetris.garbage_height(), 3);
+        assert_eq!(game.incoming.total(), 0);
+    }
+}
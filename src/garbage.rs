@@ -0,0 +1,253 @@
+use crate::randomizer::rng_from_seed;
+use crate::scoring::{LockResult, Scoring, TSpin};
+use rand::{Rng, XorShiftRng};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+// Cell value of garbage blocks, pieces use 1 to 7
+pub const GARBAGE_CELL: u8 = 8;
+
+// extra lines sent by a running combo, the last entry repeats for longer ones
+const COMBO_ATTACK: [u32; 12] = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5];
+const BACK_TO_BACK_ATTACK: u32 = 1;
+const PERFECT_CLEAR_ATTACK: u32 = 10;
+
+/*
+ * Lines sent by a lock, `scoring` being already updated with it so that its
+ * combo and back-to-back state include this clear.
+ */
+pub fn attack(result: &LockResult, scoring: &Scoring) -> u32 {
+    if result.lines == 0 {
+        return 0;
+    }
+    let mut lines = match (result.t_spin, result.lines) {
+        (TSpin::None, 4) => 4,
+        (TSpin::None, lines) => lines - 1,
+        (TSpin::Mini, lines) => lines - 1,
+        (TSpin::Full, lines) => lines * 2,
+    };
+    if scoring.last_back_to_back {
+        lines += BACK_TO_BACK_ATTACK;
+    }
+    if scoring.combo > 0 {
+        let combo = (scoring.combo as usize).min(COMBO_ATTACK.len() - 1);
+        lines += COMBO_ATTACK[combo];
+    }
+    if result.perfect_clear {
+        lines += PERFECT_CLEAR_ATTACK;
+    }
+    lines
+}
+
+// Lines received in one attack, waiting to rise
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncomingGarbage {
+    pub lines: u32,
+    // time left before the lines can rise
+    pub delay: Duration,
+}
+
+/*
+ * Garbage sent by the opponents, oldest first. Attacks of the player cancel
+ * it before anything is sent back, what is left rises once its delay is over
+ * and the player locks a piece without clearing lines.
+ */
+pub struct GarbageQueue {
+    pub incoming: VecDeque<IncomingGarbage>,
+    delay: Duration,
+}
+
+impl GarbageQueue {
+    pub fn new(delay: Duration) -> GarbageQueue {
+        GarbageQueue {
+            incoming: VecDeque::new(),
+            delay,
+        }
+    }
+
+    pub fn push(&mut self, lines: u32) {
+        if lines > 0 {
+            self.incoming.push_back(IncomingGarbage { lines, delay: self.delay });
+        }
+    }
+
+    pub fn update(&mut self, dt: Duration) {
+        for garbage in self.incoming.iter_mut() {
+            garbage.delay = garbage.delay.saturating_sub(dt);
+        }
+    }
+
+    // Removes up to `attack` lines, oldest first, and returns what is left of the attack
+    pub fn cancel(&mut self, mut attack: u32) -> u32 {
+        while attack > 0 {
+            let garbage = match self.incoming.front_mut() {
+                Some(garbage) => garbage,
+                None => break,
+            };
+            let cancelled = attack.min(garbage.lines);
+            garbage.lines -= cancelled;
+            attack -= cancelled;
+            if garbage.lines == 0 {
+                self.incoming.pop_front();
+            }
+        }
+        attack
+    }
+
+    // Takes out the attacks whose delay is over, in the order they came in
+    pub fn take_ready(&mut self) -> Vec<u32> {
+        let mut ready = Vec::new();
+        while let Some(garbage) = self.incoming.front() {
+            if garbage.delay > Duration::from_millis(0) {
+                break;
+            }
+            ready.push(garbage.lines);
+            self.incoming.pop_front();
+        }
+        ready
+    }
+
+    pub fn total(&self) -> u32 {
+        self.incoming.iter().map(|garbage| garbage.lines).sum()
+    }
+}
+
+// A full row of garbage except for the hole at column `hole`
+pub fn garbage_line(width: usize, hole: usize) -> Vec<u8> {
+    (0..width).map(|x| if x == hole { 0 } else { GARBAGE_CELL }).collect()
+}
+
+/*
+ * Picks where the holes of garbage rows go. Like the randomizers it owns
+ * its state, so the hole pattern only depends on the seed.
+ */
+pub struct GarbageGenerator {
+    rng: XorShiftRng,
+    width: usize,
+    last_hole: Option<usize>,
+}
+
+impl GarbageGenerator {
+    pub fn new(seed: u64, width: usize) -> GarbageGenerator {
+        GarbageGenerator {
+            // the randomizer already uses `seed` as is
+            rng: rng_from_seed(!seed),
+            width,
+            last_hole: None,
+        }
+    }
+
+    // A column for the next hole, never the same as the previous one
+    pub fn next_hole(&mut self) -> usize {
+        let mut hole = self.rng.gen_range(0, self.width);
+        if self.width > 1 && Some(hole) == self.last_hole {
+            hole = (hole + self.rng.gen_range(1, self.width)) % self.width;
+        }
+        self.last_hole = Some(hole);
+        hole
+    }
+
+    // `count` rows sharing the same hole, like lines sent in one attack
+    pub fn chunk(&mut self, count: usize) -> Vec<Vec<u8>> {
+        let hole = self.next_hole();
+        vec![garbage_line(self.width, hole); count]
+    }
+
+    // `count` rows with one random hole each
+    pub fn lines(&mut self, count: usize) -> Vec<Vec<u8>> {
+        (0..count).map(|_| garbage_line(self.width, self.next_hole())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DELAY: Duration = Duration::from_millis(500);
+
+    fn lock(lines: u32, t_spin: TSpin) -> LockResult {
+        LockResult { lines, t_spin, perfect_clear: false }
+    }
+
+    // Attack of `result` as the first clear of a game
+    fn first_attack(result: LockResult) -> u32 {
+        let mut scoring = Scoring::new();
+        scoring.on_lock(result, 1);
+        attack(&result, &scoring)
+    }
+
+    #[test]
+    fn attack_table() {
+        let attacks: Vec<u32> = (0..=4).map(|lines| first_attack(lock(lines, TSpin::None)))
+            .collect();
+        assert_eq!(attacks, vec![0, 0, 1, 2, 4]);
+        let attacks: Vec<u32> = (0..=3).map(|lines| first_attack(lock(lines, TSpin::Full)))
+            .collect();
+        assert_eq!(attacks, vec![0, 2, 4, 6]);
+        let attacks: Vec<u32> = (0..=2).map(|lines| first_attack(lock(lines, TSpin::Mini)))
+            .collect();
+        assert_eq!(attacks, vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn attack_bonuses() {
+        let mut scoring = Scoring::new();
+        let tetris = lock(4, TSpin::None);
+        scoring.on_lock(tetris, 1);
+        scoring.on_lock(tetris, 1);
+        // back-to-back and a combo of 1
+        assert_eq!(attack(&tetris, &scoring), 4 + 1);
+        let single = lock(1, TSpin::None);
+        for _ in 0..20 {
+            scoring.on_lock(single, 1);
+        }
+        assert_eq!(attack(&single, &scoring), 5);
+        let perfect = LockResult { perfect_clear: true, ..lock(2, TSpin::None) };
+        assert_eq!(first_attack(perfect), 1 + 10);
+    }
+
+    #[test]
+    fn attacks_cancel_the_oldest_garbage_first() {
+        let mut queue = GarbageQueue::new(DELAY);
+        queue.push(3);
+        queue.push(2);
+        assert_eq!(queue.total(), 5);
+        assert_eq!(queue.cancel(4), 0);
+        assert_eq!(queue.incoming.len(), 1);
+        assert_eq!(queue.total(), 1);
+        assert_eq!(queue.cancel(3), 2);
+        assert_eq!(queue.total(), 0);
+    }
+
+    #[test]
+    fn garbage_is_ready_after_its_delay() {
+        let mut queue = GarbageQueue::new(DELAY);
+        queue.push(4);
+        queue.update(Duration::from_millis(400));
+        queue.push(0);
+        queue.push(1);
+        assert!(queue.take_ready().is_empty());
+        queue.update(Duration::from_millis(100));
+        assert_eq!(queue.take_ready(), vec![4]);
+        queue.update(DELAY);
+        assert_eq!(queue.take_ready(), vec![1]);
+        assert_eq!(queue.total(), 0);
+    }
+
+    #[test]
+    fn holes_never_repeat() {
+        let mut generator = GarbageGenerator::new(3, 10);
+        let holes: Vec<usize> = (0..100).map(|_| generator.next_hole()).collect();
+        assert!(holes.iter().all(|hole| *hole < 10));
+        assert!(holes.windows(2).all(|pair| pair[0] != pair[1]));
+    }
+
+    #[test]
+    fn chunk_rows_share_a_hole() {
+        let mut generator = GarbageGenerator::new(3, 10);
+        let rows = generator.chunk(4);
+        assert_eq!(rows.len(), 4);
+        assert!(rows.iter().all(|row| *row == rows[0]));
+        assert_eq!(rows[0].iter().filter(|cell| **cell == 0).count(), 1);
+    }
+}
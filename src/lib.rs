@@ -0,0 +1,19 @@
+/*
+ * Headless game rules: everything needed to drive a game of tetris without
+ * a window. The SDL frontend in main.rs is only one consumer of this crate.
+ */
+pub mod tetrimino;
+pub mod tetris;
+pub mod randomizer;
+pub mod scoring;
+pub mod garbage;
+pub mod mode;
+pub mod game;
+pub mod session;
+pub mod controller;
+
+pub use crate::game::{Game, GameConfig, Input};
+pub use crate::controller::{Button, Controller, Handling};
+pub use crate::mode::GameMode;
+pub use crate::session::Session;
+pub use crate::randomizer::{Randomizer, RandomizerKind};
@@ -1,20 +1,24 @@
 mod create_texture;
 mod file_io;
-mod tetrimino;
-mod tetris;
 mod event;
+mod render;
+mod screen;
 mod texture_group;
 
-use create_texture::{create_texture_rect, create_tetrimino_texture, display_game_information};
-use tetris::{Tetris, is_time_over};
-use tetrimino::create_new_tetrimino;
-use file_io::{save_highscores_and_lines, load_highscores_and_lines};
-use event::handle_events;
+use create_texture::display_menu;
+use tetris::{Controller, Game, GameMode, Session};
+use tetris::mode::TopOutPolicy;
+use file_io::{save_highscores_and_lines, load_highscores_and_lines, save_ranking, load_ranking,
+              ranking_file};
+use event::{handle_game_event, menu_action, MenuAction};
+use render::{BoardView, PieceTextures};
+use screen::{format_time, navigate, Records, Screen, Settings, RESULT_ITEMS, TITLE_ITEMS};
 
 extern crate sdl2;
 
+use sdl2::event::Event;
 use sdl2::pixels::Color;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant};
 use std::thread::sleep;
 use sdl2::render::{TextureCreator};
 use sdl2::image::{LoadTexture, InitFlag as ImageFlag};
@@ -26,41 +30,61 @@ use sdl2::mixer::{
     DEFAULT_FORMAT,
     DEFAULT_CHANNELS,
 };
-use crate::texture_group::TextureGroup;
 
-const TETRIS_HEIGHT: u32 = 40;
 const NB_HIGHSCORES: usize = 5;
 const WINDOW_WIDTH: u32 = 1600;
 const WINDOW_HEIGHT: u32 = 900;
 const HOLD_X: u32 = 80;
-const BLOCK_Y: i32 = 300;
-const BLOCK_WIDTH: u32 = TETRIS_HEIGHT * 4 + 20;
-const ARENA_X: u32 = HOLD_X + BLOCK_WIDTH + 190;
-const ARENA_WIDTH: u32 = TETRIS_HEIGHT * 10;
-const ARENA_HEIGHT: u32 = TETRIS_HEIGHT * 16;
-const NEXT_X: u32 = ARENA_X + ARENA_WIDTH + 10;
-
-
-fn print_game_information(tetris: &Tetris) {
-    let mut new_highest_highscore = true;
-    let mut new_highest_lines_sent = true;
-    if let Some((mut highscores, mut lines_sent)) = load_highscores_and_lines() {
-        new_highest_highscore = update_vec(&mut highscores, tetris.score);
-        new_highest_lines_sent = update_vec(&mut lines_sent, tetris.nb_lines);
-        if new_highest_highscore || new_highest_lines_sent {
-            save_highscores_and_lines(&highscores, &lines_sent);
+// top of the arena
+const BOARD_Y: i32 = 50;
+const MENU_X: i32 = 560;
+const MENU_Y: i32 = 150;
+
+
+// Saves the game in the high score tables, returns which records it beat
+fn save_records(game: &Game) -> Records {
+    let tetris = &game.tetris;
+    let mut records = Records::default();
+    match game.config.mode {
+        GameMode::Marathon => {
+            if let Some((mut highscores, mut lines_sent)) = load_highscores_and_lines() {
+                records.score = update_vec(&mut highscores, tetris.score);
+                records.lines = update_vec(&mut lines_sent, tetris.nb_lines);
+                if records.score || records.lines {
+                    save_highscores_and_lines(&highscores, &lines_sent);
+                }
+            } else {
+                save_highscores_and_lines(&[tetris.score], &[tetris.nb_lines]);
+                records.score = true;
+                records.lines = true;
+            }
+        }
+        GameMode::Zen | GameMode::Versus => {}
+        GameMode::Sprint | GameMode::Dig => {
+            // only finished runs have a time worth keeping
+            if game.completed {
+                records.time = update_ranking(&ranking_file(game.config.mode.name()),
+                                              game.elapsed.as_millis() as u32,
+                                              |time, entry| time < entry);
+            }
+        }
+        mode => {
+            records.score = update_ranking(&ranking_file(mode.name()), tetris.score,
+                                           |score, entry| score > entry);
+        }
+    }
+    records
+}
+
+// Value following `--<name>` on the command line, if any
+fn parse_arg<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == format!("--{}", name) {
+            return args.next().and_then(|value| value.parse().ok());
         }
-    } else {
-        save_highscores_and_lines(&[tetris.score], &[tetris.nb_lines]);
     }
-    println!("Game over...");
-    println!("Score:           {}{}",
-             tetris.score,
-             if new_highest_highscore { " [NEW HIGHSCORE]" } else { "" });
-    println!("Number of lines: {}{}",
-             tetris.nb_lines,
-             if new_highest_lines_sent { " [NEW HIGHSCORE]" } else { "" });
-    println!("Current level:   {}", tetris.current_level);
+    None
 }
 
 fn update_vec(v: &mut Vec<u32>, value: u32) -> bool {
@@ -79,15 +103,129 @@ fn update_vec(v: &mut Vec<u32>, value: u32) -> bool {
     }
 }
 
+// Inserts `value` in the ranking saved in `file_name`, before the first entry it is better than.
+// Returns whether it made the cut
+fn update_ranking(file_name: &str, value: u32, better: fn(u32, u32) -> bool) -> bool {
+    let mut ranking = load_ranking(file_name).unwrap_or_default();
+    let rank = ranking.iter().position(|entry| better(value, *entry)).unwrap_or(ranking.len());
+    if rank >= NB_HIGHSCORES {
+        return false;
+    }
+    ranking.insert(rank, value);
+    ranking.truncate(NB_HIGHSCORES);
+    save_ranking(&ranking, file_name);
+    true
+}
+
+// Title and lines of the results screen of a single player game
+fn game_results(game: &Game, records: &Records) -> (String, Vec<String>) {
+    let tetris = &game.tetris;
+    let record = |new: bool| if new { "  New record!" } else { "" };
+    let mut items = vec![
+        format!("Score:     {}{}", tetris.score, record(records.score)),
+        format!("Lines:     {}{}", tetris.nb_lines, record(records.lines)),
+        format!("Level:     {}", tetris.current_level),
+        format!("Time:      {}{}", format_time(game.elapsed), record(records.time)),
+        format!("PPS:       {:.2}", game.pieces_per_second()),
+        format!("Max combo: {}", tetris.scoring.max_combo),
+        format!("Seed:      {}", game.seed),
+    ];
+    if game.config.mode.top_out_policy() == TopOutPolicy::ClearBoard {
+        items.push(format!("Top outs:  {}", game.recoveries));
+    }
+    let title = if game.completed {
+        "Complete!"
+    } else if tetris.top_out.is_some() {
+        "Game over"
+    } else {
+        "Results"
+    };
+    (title.to_string(), items)
+}
+
+// Title and lines of the results screen of a versus game, one column per player
+fn versus_results(session: &Session) -> (String, Vec<String>) {
+    let title = match session.winner {
+        Some(winner) => format!("Player {} wins", winner + 1),
+        None => "No winner".to_string(),
+    };
+    let row = |name: &str, value: &dyn Fn(&Game) -> String| {
+        session.games.iter().fold(format!("{:<10}", name),
+                                  |row, game| format!("{}{:>12}", row, value(game)))
+    };
+    let header = (1..=session.games.len()).fold(" ".repeat(10), |row, player| {
+        format!("{}{:>12}", row, format!("Player {}", player))
+    });
+    let mut items = vec![
+        header,
+        row("Score:", &|game| game.tetris.score.to_string()),
+        row("Lines:", &|game| game.tetris.nb_lines.to_string()),
+        row("Sent:", &|game| game.lines_sent.to_string()),
+        row("PPS:", &|game| format!("{:.2}", game.pieces_per_second())),
+    ];
+    let elapsed = session.games.iter().map(|game| game.elapsed).max().unwrap_or_default();
+    items.push(format!("Time:     {}", format_time(elapsed)));
+    items.push(format!("Seed:     {}", session.games[0].seed));
+    (title, items)
+}
+
+// Modes with a high score table
+fn ranked_modes() -> Vec<GameMode> {
+    GameMode::ALL.iter().cloned().filter(|mode| mode.keeps_records()).collect()
+}
+
+// Rows of the high score table of `mode`
+fn high_score_items(mode: GameMode) -> Vec<String> {
+    let mut items = Vec::new();
+    match mode {
+        GameMode::Sprint | GameMode::Dig => {
+            items.push("  Rank   Time".to_string());
+            let times = load_ranking(&ranking_file(mode.name())).unwrap_or_default();
+            for (rank, time) in times.iter().enumerate() {
+                items.push(format!("{:>6}   {}", rank + 1,
+                                   format_time(Duration::from_millis(*time as u64))));
+            }
+        }
+        GameMode::Marathon => {
+            items.push("  Score   Lines".to_string());
+            if let Some((mut highscores, mut lines_sent)) = load_highscores_and_lines() {
+                highscores.sort_by(|a, b| b.cmp(a));
+                lines_sent.sort_by(|a, b| b.cmp(a));
+                for rank in 0..highscores.len().max(lines_sent.len()) {
+                    let entry = |v: &Vec<u32>| v.get(rank)
+                        .map_or(String::new(), |value| value.to_string());
+                    items.push(format!("{:>7} {:>7}", entry(&highscores), entry(&lines_sent)));
+                }
+            }
+        }
+        GameMode::Zen | GameMode::Versus => {}
+        mode => {
+            items.push("  Rank   Score".to_string());
+            let scores = load_ranking(&ranking_file(mode.name())).unwrap_or_default();
+            for (rank, score) in scores.iter().enumerate() {
+                items.push(format!("{:>6}   {}", rank + 1, score));
+            }
+        }
+    }
+    items
+}
+
 fn main() {
     let sdl_context = sdl2::init().expect("SDL initialization failed");
     let video_subsystem = sdl_context.video().expect("Couldn't get SDL video subsystem");
-    let mut timer = SystemTime::now();
+    let controller_subsystem = sdl_context.game_controller()
+        .expect("Couldn't get SDL game controller subsystem");
     let mut event_pump = sdl_context.event_pump().expect("Failed to get SDL event pump");
 
-    let grid_x = ARENA_X as i32;
-    let grid_y = (WINDOW_HEIGHT - ARENA_HEIGHT) as i32 / 2;
-    let mut tetris = Tetris::new();
+    let mut settings = Settings {
+        config: Default::default(),
+        handling: Default::default(),
+        // `--seed <u64>` replays a given piece sequence, otherwise pick a fresh one
+        seed: parse_arg("seed"),
+    };
+    settings.config.width = parse_arg("width").unwrap_or(settings.config.width).max(4);
+    settings.config.height = parse_arg("height").unwrap_or(settings.config.height).max(4);
+    settings.config.next_count = parse_arg("next").unwrap_or(settings.config.next_count);
 
     let window =
         video_subsystem
@@ -122,200 +260,226 @@ fn main() {
     let music = Music::from_file("assets/theme.ogg").expect("Couldn't load theme song");
     music.play(-1).expect("Couldn't play theme song");
 
-    let mut arena = TextureGroup::new();
-    arena.add(create_texture_rect(
-        &mut canvas,
-        &texture_creator,
-        255, 255, 255,
-        ARENA_WIDTH + 20,
-        ARENA_HEIGHT + 20,
-    ).expect("Failed to create a texture"), -10, -10);
-    arena.add(create_texture_rect(
-        &mut canvas,
-        &texture_creator,
-        0, 0, 0,
-        ARENA_WIDTH,
-        ARENA_HEIGHT,
-    ).expect("Failed to create a texture"), 0, 0);
-
-    let mut small_preview_area = TextureGroup::new();
-    small_preview_area.add(create_texture_rect(
-        &mut canvas,
-        &texture_creator,
-        255, 255, 255,
-        BLOCK_WIDTH + 20,
-        BLOCK_WIDTH + 20,
-    ).expect("Failed to create a texture"), -10, -10);
-
-    small_preview_area.add(create_texture_rect(
-        &mut canvas,
-        &texture_creator,
-        0, 0, 0,
-        BLOCK_WIDTH,
-        BLOCK_WIDTH,
-    ).expect("Failed to create a texture"), 0, 0);
-
     let ttf_context = sdl2::ttf::init().expect("SDL TTF initialization failed");
     let font = ttf_context.load_font(
         "assets/JetBrainsMonoNL-Regular.ttf",
         128,
     ).expect("Couldn't load the font");
 
-    macro_rules! texture {
-        ($r:expr, $g:expr, $b:expr) => (
-            create_tetrimino_texture(
-                &mut canvas,
-                &texture_creator,
-                $r, $g, $b
-            )
-        )
-      }
-
-    let colour_of_piece = [
-        (255, 69, 69), (255, 220, 69),
-        (237, 150, 37), (171, 99, 237),
-        (77, 149, 239), (39, 218, 225),
-        (45, 216, 47)
-    ];
+    let mut textures = PieceTextures::new(&mut canvas, &texture_creator);
 
-    let textures: Vec<TextureGroup> = colour_of_piece
-        .iter()
-        .map(|c| texture!(c.0, c.1, c.2))
-        .collect();
-
-    let textures_alpha: Vec<TextureGroup> = colour_of_piece
-        .iter()
-        .map(|c| {
-            let mut t = texture!(c.0, c.1, c.2);
-            t.set_alpha(70);
-            t
-        })
-        .collect();
-
-    loop {
-        if is_time_over(&tetris, &timer) {
-            let mut make_permanent = false;
-            if let Some(ref mut piece) = tetris.current_piece {
-                let x = piece.x;
-                let y = piece.y + 1;
-                make_permanent = !piece.change_position(&tetris.game_map,
-                                                        x, y);
+    // controllers have to stay open for their events to come in
+    let mut pads = Vec::new();
+    let mut screen = Screen::Title;
+    let mut selected = 0;
+    let mut session: Option<Session> = None;
+    // one per player
+    let mut board_views: Vec<BoardView> = Vec::new();
+    let mut controllers: Vec<Controller> = Vec::new();
+    let mut records = Records::default();
+    let mut last_frame = Instant::now();
+    'running: loop {
+        let events: Vec<Event> = event_pump.poll_iter().collect();
+        let mut inputs = vec![Vec::new(); controllers.len()];
+        let mut give_up = false;
+        let mut start_mode = None;
+
+        for event in events.iter() {
+            match *event {
+                Event::Quit { .. } => break 'running,
+                Event::ControllerDeviceAdded { which, .. } => {
+                    if let Ok(pad) = controller_subsystem.open(which) {
+                        pads.push(pad);
+                    }
+                    continue;
+                }
+                _ => {}
             }
-            if make_permanent {
-                tetris.make_permanent();
+            if screen == Screen::InGame {
+                if let Some(ref mut session) = session {
+                    handle_game_event(event, &pads, &mut give_up, session, &mut controllers,
+                                      &mut inputs);
+                }
+                continue;
             }
-            timer = SystemTime::now();
-        }
-
-        canvas.set_draw_color(Color::RGB(255, 0, 0));
-        canvas.clear();
-        //background
-        canvas.copy(&image_texture, None, None).expect("Render failed");
 
-        //hold piece
-        small_preview_area.copy_to_canvas(&mut canvas, HOLD_X as i32, BLOCK_Y);
-        //arena
-        arena.copy_to_canvas(&mut canvas, ARENA_X as i32, (WINDOW_HEIGHT - ARENA_HEIGHT) as i32 / 2);
-        //next piece
-        small_preview_area.copy_to_canvas(&mut canvas, NEXT_X as i32, BLOCK_Y);
-
-        if tetris.current_piece.is_none() {
-            if !&tetris.next_piece.test_current_position(&tetris.game_map) {
-                print_game_information(&tetris);
-                break;
-            }
-            tetris.current_piece = Some(tetris.next_piece);
-            tetris.next_piece = create_new_tetrimino();
-        }
-        let mut quit = false;
-
-        // current piece
-        if !handle_events(&mut tetris, &mut quit, &mut timer,
-                          &mut event_pump) {
-            if let Some(ref mut piece) = tetris.current_piece {
-                for (line_nb, line) in piece.states[piece.current_state
-                    as usize].iter().enumerate() {
-                    for (case_nb, case) in line.iter().enumerate() {
-                        if *case == 0 {
-                            continue;
+            let action = match menu_action(event) {
+                Some(action) => action,
+                None => continue,
+            };
+            let previous_screen = screen;
+            match screen {
+                Screen::Title => {
+                    navigate(&mut selected, TITLE_ITEMS.len(), action);
+                    if action == MenuAction::Confirm {
+                        match selected {
+                            0 => screen = Screen::ModeSelect,
+                            1 => screen = Screen::Settings,
+                            2 => screen = Screen::HighScores,
+                            _ => break 'running,
                         }
-                        textures[*case as usize - 1].copy_to_canvas(
-                            &mut canvas,
-                            grid_x + (piece.x + case_nb as isize) as i32 * TETRIS_HEIGHT as i32,
-                            grid_y + (piece.y + line_nb) as i32 * TETRIS_HEIGHT as i32);
                     }
                 }
+                Screen::ModeSelect => {
+                    // every mode, then "Back"
+                    navigate(&mut selected, GameMode::ALL.len() + 1, action);
+                    match action {
+                        MenuAction::Confirm if selected < GameMode::ALL.len() =>
+                            start_mode = Some(GameMode::ALL[selected]),
+                        MenuAction::Confirm | MenuAction::Back => screen = Screen::Title,
+                        _ => {}
+                    }
+                }
+                Screen::Settings => {
+                    // every setting, then "Back"
+                    navigate(&mut selected, Settings::LEN + 1, action);
+                    match action {
+                        MenuAction::Left => settings.adjust(selected, -1),
+                        MenuAction::Right => settings.adjust(selected, 1),
+                        MenuAction::Confirm if selected < Settings::LEN => settings.adjust(selected, 1),
+                        MenuAction::Confirm | MenuAction::Back => screen = Screen::Title,
+                        _ => {}
+                    }
+                }
+                Screen::HighScores => {
+                    // left and right go through the tables of every mode
+                    let modes = ranked_modes().len();
+                    match action {
+                        MenuAction::Left => selected = (selected + modes - 1) % modes,
+                        MenuAction::Right => selected = (selected + 1) % modes,
+                        MenuAction::Confirm | MenuAction::Back => screen = Screen::Title,
+                        _ => {}
+                    }
+                }
+                Screen::GameOver => {
+                    navigate(&mut selected, RESULT_ITEMS.len(), action);
+                    match action {
+                        MenuAction::Confirm if selected == 0 => start_mode = session.as_ref()
+                            .map(|session| session.games[0].config.mode),
+                        MenuAction::Confirm | MenuAction::Back => screen = Screen::Title,
+                        _ => {}
+                    }
+                }
+                Screen::InGame => {}
+            }
+            if screen != previous_screen {
+                selected = 0;
             }
         }
-        if quit {
-            print_game_information(&tetris);
-            break;
+
+        if let Some(mode) = start_mode {
+            let seed = settings.seed.unwrap_or_else(rand::random);
+            let new_session = Session::new(settings.config_for(mode), seed, mode.players());
+            let players = new_session.games.len() as u32;
+
+            // boards side by side, the window grows for those that don't fit the default one
+            let (arena_width, arena_height) = BoardView::arena_size(&new_session.games[0]);
+            let board_width = BoardView::left_margin() + arena_width + BoardView::right_margin();
+            let window_width = WINDOW_WIDTH.max(HOLD_X * (players + 1) + board_width * players);
+            let window_height = WINDOW_HEIGHT.max(BOARD_Y as u32 * 2 + arena_height)
+                .max(BOARD_Y as u32 * 2 + BoardView::min_height());
+            if canvas.window().size() != (window_width, window_height) {
+                canvas.window_mut().set_size(window_width, window_height)
+                    .expect("Couldn't resize window");
+            }
+
+            board_views = new_session.games.iter().enumerate()
+                .map(|(index, game)| {
+                    let x = HOLD_X + index as u32 * (board_width + HOLD_X) +
+                        BoardView::left_margin();
+                    BoardView::new(&mut canvas, &texture_creator, game, x as i32, BOARD_Y)
+                })
+                .collect();
+            controllers = new_session.games.iter()
+                .map(|_| Controller::new(settings.handling))
+                .collect();
+            session = Some(new_session);
+            screen = Screen::InGame;
+            selected = 0;
         }
-        // hold_piece
-        if let Some(ref mut piece) = tetris.holding_piece {
-            for (line_nb, line) in piece.states[piece.current_state
-                as usize].iter().enumerate() {
-                for (case_nb, case) in line.iter().enumerate() {
-                    if *case == 0 {
-                        continue;
+
+        let now = Instant::now();
+        let dt = now - last_frame;
+        last_frame = now;
+        if screen == Screen::InGame {
+            if let Some(ref mut session) = session {
+                inputs.resize(controllers.len(), Vec::new());
+                for (index, game) in session.games.iter().enumerate() {
+                    if !game.paused {
+                        let gravity_delay = game.tetris.gravity_delay();
+                        inputs[index].extend(controllers[index].update(dt, gravity_delay));
                     }
-                    textures[*case as usize - 1].copy_to_canvas(
-                        &mut canvas,
-                        HOLD_X as i32 + 20 + case_nb as i32 * TETRIS_HEIGHT as i32,
-                        BLOCK_Y + 20 + line_nb as i32 * TETRIS_HEIGHT as i32);
+                }
+                session.step(&inputs, dt);
+                if session.is_over() || give_up {
+                    records = if session.is_versus() {
+                        Records::default()
+                    } else {
+                        save_records(&session.games[0])
+                    };
+                    screen = Screen::GameOver;
+                    selected = 0;
                 }
             }
         }
 
-        // next_piece
-        for (line_nb, line) in tetris.next_piece.states[tetris.next_piece.current_state
-            as usize].iter().enumerate() {
-            for (case_nb, case) in line.iter().enumerate() {
-                if *case == 0 {
-                    continue;
-                }
-                textures[*case as usize - 1].copy_to_canvas(
-                    &mut canvas,
-                    NEXT_X as i32 + 20 + case_nb as i32 * TETRIS_HEIGHT as i32,
-                    BLOCK_Y + 20 + line_nb as i32 * TETRIS_HEIGHT as i32);
+        canvas.set_draw_color(Color::RGB(255, 0, 0));
+        canvas.clear();
+        //background
+        canvas.copy(&image_texture, None, None).expect("Render failed");
+
+        match screen {
+            Screen::Title => {
+                let items: Vec<String> = TITLE_ITEMS.iter().map(|item| item.to_string()).collect();
+                display_menu(&mut canvas, &texture_creator, &font, "TETRIS",
+                             &items, selected, MENU_X, MENU_Y);
             }
-        }
-        // ghost
-        if let Some(ref current_piece) = tetris.current_piece {
-            let mut piece = current_piece.clone();
-            let mut y = piece.y;
-            while piece.change_position(&tetris.game_map, piece.x, y + 1)
-                == true {
-                y += 1;
+            Screen::ModeSelect => {
+                let mut items: Vec<String> = GameMode::ALL.iter()
+                    .map(|mode| format!("{:<10} {}", mode.name(), mode.description()))
+                    .collect();
+                items.push("Back".to_string());
+                display_menu(&mut canvas, &texture_creator, &font, "Mode",
+                             &items, selected, MENU_X, MENU_Y);
+            }
+            Screen::Settings => {
+                let mut items = settings.items();
+                items.push("Back".to_string());
+                display_menu(&mut canvas, &texture_creator, &font, "Settings",
+                             &items, selected, MENU_X, MENU_Y);
             }
-            for (line_nb, line) in piece.states[piece.current_state
-                as usize].iter().enumerate() {
-                for (case_nb, case) in line.iter().enumerate() {
-                    if *case == 0 {
-                        continue;
+            Screen::HighScores => {
+                let mode = ranked_modes()[selected];
+                let mut items = high_score_items(mode);
+                items.push(String::new());
+                items.push("< Mode >  Back".to_string());
+                let back = items.len() - 1;
+                display_menu(&mut canvas, &texture_creator, &font,
+                             &format!("{} records", mode.name()),
+                             &items, back, MENU_X, MENU_Y);
+            }
+            Screen::InGame => {
+                if let Some(ref session) = session {
+                    for (board_view, game) in board_views.iter().zip(session.games.iter()) {
+                        board_view.draw(&mut canvas, &texture_creator, &font, &mut textures, game);
                     }
-                    textures_alpha[*case as usize - 1].copy_to_canvas(
-                        &mut canvas,
-                        grid_x + (piece.x + case_nb as isize) as i32 * TETRIS_HEIGHT as i32,
-                        grid_y + (piece.y + line_nb) as i32 * TETRIS_HEIGHT as i32);
                 }
             }
-        }
-
-        // fallen pieces
-        for (line_nb, line) in tetris.game_map.iter().enumerate() {
-            for (case_nb, case) in line.iter().enumerate() {
-                if *case == 0 {
-                    continue;
+            Screen::GameOver => {
+                if let Some(ref session) = session {
+                    let (title, mut items) = if session.is_versus() {
+                        versus_results(session)
+                    } else {
+                        game_results(&session.games[0], &records)
+                    };
+                    items.push(String::new());
+                    let first_option = items.len();
+                    items.extend(RESULT_ITEMS.iter().map(|item| item.to_string()));
+                    display_menu(&mut canvas, &texture_creator, &font, &title,
+                                 &items, first_option + selected, MENU_X, MENU_Y);
                 }
-                textures[*case as usize - 1].copy_to_canvas(
-                    &mut canvas,
-                    grid_x + case_nb as i32 * TETRIS_HEIGHT as i32,
-                    grid_y + line_nb as i32 * TETRIS_HEIGHT as i32);
             }
         }
-        display_game_information(&tetris, &mut canvas, &texture_creator, &font,
-                                 NEXT_X as i32, BLOCK_Y - 120);
         canvas.present();
 
         sleep(Duration::new(0, 1_000_000_000u32 / 60));
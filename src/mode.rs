@@ -0,0 +1,114 @@
+use crate::tetris::{LevelCurve, MARATHON_CURVE, MASTER_CURVE};
+
+// What happens when a player tops out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopOutPolicy {
+    EndGame,
+    // the board is emptied and the game goes on
+    ClearBoard,
+}
+
+// Rule sets a game can be played with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    // endless, levels speed up gravity until the player tops out
+    Marathon,
+    // clear 40 lines as fast as possible
+    Sprint,
+    // score as much as possible before time runs out
+    Ultra,
+    // clear garbage rows from the bottom as fast as possible
+    Dig,
+    // endless, quickly reaches 20G then gets faster every level
+    Master,
+    // endless practice, topping out only clears the board
+    Zen,
+    // locked pieces fade out after a while
+    Fading,
+    // locked pieces can't be seen at all
+    Invisible,
+    // every mino takes 2x2 cells of the board
+    Big,
+    // two players on one screen sending garbage to each other
+    Versus,
+}
+
+pub const SPRINT_LINES: u32 = 40;
+// garbage rows on the board at once in Dig, as long as enough are left to dig
+pub const DIG_ROWS: usize = 10;
+
+impl GameMode {
+    pub const ALL: [GameMode; 10] = [
+        GameMode::Marathon, GameMode::Sprint, GameMode::Ultra, GameMode::Dig, GameMode::Master,
+        GameMode::Zen, GameMode::Fading, GameMode::Invisible, GameMode::Big, GameMode::Versus,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Marathon",
+            GameMode::Sprint => "Sprint",
+            GameMode::Ultra => "Ultra",
+            GameMode::Dig => "Dig",
+            GameMode::Master => "Master",
+            GameMode::Zen => "Zen",
+            GameMode::Fading => "Fading",
+            GameMode::Invisible => "Invisible",
+            GameMode::Big => "Big",
+            GameMode::Versus => "Versus",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            GameMode::Marathon => "Endless, speeds up every level",
+            GameMode::Sprint => "Clear 40 lines as fast as possible",
+            GameMode::Ultra => "Score as much as possible in time",
+            GameMode::Dig => "Dig through garbage as fast as possible",
+            GameMode::Master => "Endless 20G, shorter delays every level",
+            GameMode::Zen => "Relaxed practice, no game over",
+            GameMode::Fading => "Marathon where the stack fades away",
+            GameMode::Invisible => "Marathon with an invisible stack",
+            GameMode::Big => "Marathon with double size pieces",
+            GameMode::Versus => "Two players, last one standing wins",
+        }
+    }
+
+    // Whether clearing lines raises the level and with it the gravity
+    pub fn levels_up(self) -> bool {
+        !matches!(self, GameMode::Sprint | GameMode::Ultra | GameMode::Dig | GameMode::Zen)
+    }
+
+    pub fn level_curve(self) -> LevelCurve {
+        match self {
+            GameMode::Master => MASTER_CURVE,
+            _ => MARATHON_CURVE,
+        }
+    }
+
+    // Map cells per side of a mino
+    pub fn scale(self) -> usize {
+        match self {
+            GameMode::Big => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn top_out_policy(self) -> TopOutPolicy {
+        match self {
+            GameMode::Zen => TopOutPolicy::ClearBoard,
+            _ => TopOutPolicy::EndGame,
+        }
+    }
+
+    pub fn players(self) -> usize {
+        match self {
+            GameMode::Versus => 2,
+            _ => 1,
+        }
+    }
+
+    // Practice and multiplayer modes have no high score table
+    pub fn keeps_records(self) -> bool {
+        !matches!(self, GameMode::Zen | GameMode::Versus)
+    }
+}
@@ -0,0 +1,198 @@
+use crate::tetrimino::TetriminoKind;
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+/*
+ * Decides the order in which pieces come out. Each game owns its own
+ * randomizer, so several games can run side by side without sharing state,
+ * and the whole sequence is determined by the seed it was created with.
+ */
+pub trait Randomizer {
+    fn next_kind(&mut self) -> TetriminoKind;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RandomizerKind {
+    SevenBag,
+    FourteenBag,
+    Classic,
+    Random,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 4] = [
+        RandomizerKind::SevenBag, RandomizerKind::FourteenBag,
+        RandomizerKind::Classic, RandomizerKind::Random,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RandomizerKind::SevenBag => "7-bag",
+            RandomizerKind::FourteenBag => "14-bag",
+            RandomizerKind::Classic => "Classic",
+            RandomizerKind::Random => "Random",
+        }
+    }
+
+    pub fn create(self, seed: u64) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::SevenBag => Box::new(BagRandomizer::new(1, seed)),
+            RandomizerKind::FourteenBag => Box::new(BagRandomizer::new(2, seed)),
+            RandomizerKind::Classic => Box::new(ClassicRandomizer::new(seed)),
+            RandomizerKind::Random => Box::new(PureRandomizer::new(seed)),
+        }
+    }
+}
+
+// Spreads the seed over the four xorshift words with splitmix64
+pub fn rng_from_seed(seed: u64) -> XorShiftRng {
+    let mut state = seed;
+    let mut words = [0u32; 4];
+    for word in words.iter_mut() {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        *word = (z ^ (z >> 31)) as u32;
+    }
+    if words == [0; 4] {
+        // xorshift can't be seeded with all zeroes
+        words[0] = 1;
+    }
+    XorShiftRng::from_seed(words)
+}
+
+// Deals every piece `copies` times in a shuffled bag before refilling it
+pub struct BagRandomizer {
+    copies: usize,
+    bag: Vec<TetriminoKind>,
+    rng: XorShiftRng,
+}
+
+impl BagRandomizer {
+    pub fn new(copies: usize, seed: u64) -> BagRandomizer {
+        BagRandomizer {
+            copies,
+            bag: Vec::new(),
+            rng: rng_from_seed(seed),
+        }
+    }
+}
+
+impl Randomizer for BagRandomizer {
+    fn next_kind(&mut self) -> TetriminoKind {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend_from_slice(&TetriminoKind::ALL);
+            }
+            self.rng.shuffle(&mut self.bag);
+        }
+        self.bag.pop().unwrap()
+    }
+}
+
+// NES style: roll one of 8, reroll once on a repeat or on the dummy 8th value
+pub struct ClassicRandomizer {
+    prev: Option<TetriminoKind>,
+    rng: XorShiftRng,
+}
+
+impl ClassicRandomizer {
+    pub fn new(seed: u64) -> ClassicRandomizer {
+        ClassicRandomizer {
+            prev: None,
+            rng: rng_from_seed(seed),
+        }
+    }
+}
+
+impl Randomizer for ClassicRandomizer {
+    fn next_kind(&mut self) -> TetriminoKind {
+        let roll = self.rng.gen_range(0, TetriminoKind::ALL.len() + 1);
+        let mut kind = TetriminoKind::ALL.get(roll).copied();
+        if kind.is_none() || kind == self.prev {
+            kind = Some(TetriminoKind::ALL[self.rng.gen_range(0, TetriminoKind::ALL.len())]);
+        }
+        self.prev = kind;
+        kind.unwrap()
+    }
+}
+
+pub struct PureRandomizer {
+    rng: XorShiftRng,
+}
+
+impl PureRandomizer {
+    pub fn new(seed: u64) -> PureRandomizer {
+        PureRandomizer {
+            rng: rng_from_seed(seed),
+        }
+    }
+}
+
+impl Randomizer for PureRandomizer {
+    fn next_kind(&mut self) -> TetriminoKind {
+        TetriminoKind::ALL[self.rng.gen_range(0, TetriminoKind::ALL.len())]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [RandomizerKind; 4] = [
+        RandomizerKind::SevenBag, RandomizerKind::FourteenBag,
+        RandomizerKind::Classic, RandomizerKind::Random,
+    ];
+
+    fn sequence(kind: RandomizerKind, seed: u64, len: usize) -> Vec<TetriminoKind> {
+        let mut randomizer = kind.create(seed);
+        (0..len).map(|_| randomizer.next_kind()).collect()
+    }
+
+    fn count(pieces: &[TetriminoKind], kind: TetriminoKind) -> usize {
+        pieces.iter().filter(|piece| **piece == kind).count()
+    }
+
+    #[test]
+    fn seven_bag_deals_full_sets() {
+        for seed in 0..20 {
+            for bag in sequence(RandomizerKind::SevenBag, seed, 70).chunks(7) {
+                for kind in TetriminoKind::ALL.iter() {
+                    assert_eq!(count(bag, *kind), 1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fourteen_bag_deals_every_piece_twice() {
+        for bag in sequence(RandomizerKind::FourteenBag, 3, 70).chunks(14) {
+            for kind in TetriminoKind::ALL.iter() {
+                assert_eq!(count(bag, *kind), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn classic_rarely_repeats() {
+        // a repeat needs the reroll to land on it again
+        let pieces = sequence(RandomizerKind::Classic, 5, 700);
+        let repeats = pieces.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        assert!(repeats < 700 / 7);
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        for kind in KINDS.iter() {
+            assert_eq!(sequence(*kind, 42, 100), sequence(*kind, 42, 100));
+            assert_ne!(sequence(*kind, 42, 100), sequence(*kind, 43, 100));
+        }
+    }
+
+    #[test]
+    fn zero_seed_is_usable() {
+        // xorshift is stuck on zero if the seed reaches it unmixed
+        let pieces = sequence(RandomizerKind::Random, 0, 100);
+        assert!(pieces.windows(2).any(|pair| pair[0] != pair[1]));
+    }
+}
@@ -0,0 +1,346 @@
+use crate::create_texture::{create_outline_texture, create_texture_rect, create_tetrimino_texture,
+                            display_game_information, display_text};
+use crate::screen::format_time;
+use crate::texture_group::TextureGroup;
+use sdl2::pixels::Color;
+use sdl2::render::{Canvas, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::time::Duration;
+use tetris::Game;
+use tetris::tetrimino::Tetrimino;
+use tetris::tetris::{Goal, BUFFER_HEIGHT, MAX_NEXT_COUNT};
+
+pub const TETRIS_HEIGHT: u32 = 40;
+const SMALL_TETRIS_HEIGHT: u32 = TETRIS_HEIGHT / 2;
+const BLOCK_WIDTH: u32 = TETRIS_HEIGHT * 4 + 20;
+// space between the hold box and the arena
+const HOLD_GAP: u32 = 190;
+// hold and next boxes start this far below the top of the arena
+const BLOCK_Y: i32 = 250;
+// room for one small piece in the queue, pieces in spawn rotation are at most 2 cells tall
+const QUEUE_ENTRY_HEIGHT: u32 = SMALL_TETRIS_HEIGHT * 3;
+const QUEUE_Y: i32 = BLOCK_Y + BLOCK_WIDTH as i32 + 20;
+// how long the outline of a hidden stack shows after a line clear
+const CLEAR_FLASH: Duration = Duration::from_millis(300);
+// incoming garbage meter, between the hold box and the arena
+const METER_WIDTH: u32 = 12;
+const METER_GAP: i32 = 10;
+
+// indexed by cell value - 1, the last one is garbage
+const COLOUR_OF_PIECE: [(u8, u8, u8); 8] = [
+    (255, 69, 69), (255, 220, 69),
+    (237, 150, 37), (171, 99, 237),
+    (77, 149, 239), (39, 218, 225),
+    (45, 216, 47), (150, 150, 150)
+];
+
+// One texture per piece colour, in every variant a board needs
+pub struct PieceTextures<'a> {
+    pub normal: Vec<TextureGroup<'a>>,
+    // later entries of the next queue are drawn smaller
+    pub small: Vec<TextureGroup<'a>>,
+    // hold box while hold is unavailable
+    pub grey: Vec<TextureGroup<'a>>,
+    pub ghost: Vec<TextureGroup<'a>>,
+    // stack cells of modes where it fades, their alpha changes for every cell
+    pub fading: Vec<TextureGroup<'a>>,
+    pub outline: TextureGroup<'a>,
+    // one line of the garbage meter, ready to rise or still delayed
+    pub garbage_ready: TextureGroup<'a>,
+    pub garbage_waiting: TextureGroup<'a>,
+}
+
+impl<'a> PieceTextures<'a> {
+    pub fn new(canvas: &mut Canvas<Window>,
+               texture_creator: &'a TextureCreator<WindowContext>) -> PieceTextures<'a> {
+        macro_rules! textures {
+            ($colour:expr, $size:expr) => (
+                COLOUR_OF_PIECE
+                    .iter()
+                    .map(|c| {
+                        let (r, g, b) = $colour(*c);
+                        create_tetrimino_texture(canvas, texture_creator, r, g, b, $size)
+                    })
+                    .collect::<Vec<TextureGroup>>()
+            )
+        }
+
+        let normal = textures!(|c| c, TETRIS_HEIGHT);
+        let small = textures!(|c| c, SMALL_TETRIS_HEIGHT);
+        let grey = textures!(|_| (110, 110, 110), TETRIS_HEIGHT);
+        let mut ghost = textures!(|c| c, TETRIS_HEIGHT);
+        for texture in ghost.iter_mut() {
+            texture.set_alpha(70);
+        }
+        let fading = textures!(|c| c, TETRIS_HEIGHT);
+        let outline = create_outline_texture(canvas, texture_creator, 255, 255, 255, TETRIS_HEIGHT);
+        let mut meter = |r, g, b| {
+            let mut texture = TextureGroup::new();
+            texture.add(create_texture_rect(canvas, texture_creator, r, g, b,
+                                            METER_WIDTH, TETRIS_HEIGHT - 2).unwrap(), 0, 1);
+            texture
+        };
+        let garbage_ready = meter(255, 69, 69);
+        let garbage_waiting = meter(255, 220, 69);
+        PieceTextures { normal, small, grey, ghost, fading, outline, garbage_ready,
+                        garbage_waiting }
+    }
+}
+
+/*
+ * Frames and position of one board: hold box, arena, next queue and
+ * information. The arena's top left corner is at (x, y).
+ */
+pub struct BoardView<'a> {
+    pub x: i32,
+    pub y: i32,
+    arena_width: u32,
+    arena_height: u32,
+    arena: TextureGroup<'a>,
+    preview_area: TextureGroup<'a>,
+    queue_area: Option<TextureGroup<'a>>,
+}
+
+impl<'a> BoardView<'a> {
+    pub fn new(canvas: &mut Canvas<Window>,
+               texture_creator: &'a TextureCreator<WindowContext>,
+               game: &Game, x: i32, y: i32) -> BoardView<'a> {
+        let arena_width = TETRIS_HEIGHT * game.tetris.width as u32;
+        let arena_height = TETRIS_HEIGHT * game.tetris.height as u32;
+        let arena = framed_rect(canvas, texture_creator, arena_width, arena_height);
+        let preview_area = framed_rect(canvas, texture_creator, BLOCK_WIDTH, BLOCK_WIDTH);
+
+        // box for the rest of the next queue, if it is longer than one piece
+        let queue_length = game.tetris.next_pieces.len() as u32;
+        let queue_area = if queue_length > 1 {
+            let queue_height = (queue_length - 1) * QUEUE_ENTRY_HEIGHT + 20;
+            Some(framed_rect(canvas, texture_creator, BLOCK_WIDTH, queue_height))
+        } else {
+            None
+        };
+
+        BoardView {
+            x,
+            y,
+            arena_width,
+            arena_height,
+            arena,
+            preview_area,
+            queue_area,
+        }
+    }
+
+    // Space taken left of the arena by the hold box
+    pub fn left_margin() -> u32 {
+        BLOCK_WIDTH + HOLD_GAP
+    }
+
+    // Space taken right of the arena by the next queue
+    pub fn right_margin() -> u32 {
+        BLOCK_WIDTH + 10
+    }
+
+    pub fn arena_size(game: &Game) -> (u32, u32) {
+        (TETRIS_HEIGHT * game.tetris.width as u32, TETRIS_HEIGHT * game.tetris.height as u32)
+    }
+
+    // Height needed below the arena's top for the longest next queue
+    pub fn min_height() -> u32 {
+        QUEUE_Y as u32 + (MAX_NEXT_COUNT as u32 - 1) * QUEUE_ENTRY_HEIGHT + 40
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas<Window>,
+                texture_creator: &TextureCreator<WindowContext>,
+                font: &sdl2::ttf::Font,
+                textures: &mut PieceTextures,
+                game: &Game) {
+        let tetris = &game.tetris;
+        let hold_x = self.x - Self::left_margin() as i32;
+        let next_x = self.x + self.arena_width as i32 + 10;
+
+        //hold piece
+        self.preview_area.copy_to_canvas(canvas, hold_x, self.y + BLOCK_Y);
+        //arena
+        self.arena.copy_to_canvas(canvas, self.x, self.y);
+        //next piece
+        self.preview_area.copy_to_canvas(canvas, next_x, self.y + BLOCK_Y);
+        if let Some(ref queue_area) = self.queue_area {
+            queue_area.copy_to_canvas(canvas, next_x, self.y + QUEUE_Y);
+        }
+
+        if game.paused {
+            // the board stays hidden so pausing can't be used to plan ahead
+            display_text(canvas, texture_creator, font, "Paused", Color::RGB(255, 255, 255),
+                         self.x + self.arena_width as i32 / 2 - 60,
+                         self.y + self.arena_height as i32 / 2 - 15);
+        } else {
+            // current piece
+            if let Some(ref piece) = tetris.current_piece {
+                self.draw_in_arena(canvas, &textures.normal, piece);
+            }
+            // hold_piece, greyed out until the current piece locks
+            if let Some(ref piece) = tetris.holding_piece {
+                let textures = if tetris.can_hold { &textures.normal } else { &textures.grey };
+                draw_preview(canvas, textures, TETRIS_HEIGHT, piece,
+                             hold_x + 20, self.y + BLOCK_Y + 20);
+            }
+
+            // next queue, the first piece in the big box and the rest below it
+            for (index, piece) in tetris.next_pieces.iter().enumerate() {
+                if index == 0 {
+                    draw_preview(canvas, &textures.normal, TETRIS_HEIGHT, piece,
+                                 next_x + 20, self.y + BLOCK_Y + 20);
+                } else {
+                    draw_preview(canvas, &textures.small, SMALL_TETRIS_HEIGHT, piece,
+                                 next_x + 20,
+                                 self.y + QUEUE_Y + 10 + (index as i32 - 1) * QUEUE_ENTRY_HEIGHT as i32);
+                }
+            }
+            // ghost
+            if let Some(ref current_piece) = tetris.current_piece {
+                let mut piece = current_piece.clone();
+                let mut y = piece.y;
+                while piece.change_position(&tetris.game_map, piece.x, y + 1) {
+                    y += 1;
+                }
+                self.draw_in_arena(canvas, &textures.ghost, &piece);
+            }
+
+            // fallen pieces
+            let fade = game.config.stack_fade();
+            let flash = fade.is_some() && tetris.last_clear
+                .is_some_and(|time| tetris.clock - time < CLEAR_FLASH);
+            for (line_nb, line) in tetris.game_map.iter().skip(BUFFER_HEIGHT).enumerate() {
+                for (case_nb, case) in line.iter().enumerate() {
+                    if *case == 0 {
+                        continue;
+                    }
+                    let x = self.x + case_nb as i32 * TETRIS_HEIGHT as i32;
+                    let y = self.y + line_nb as i32 * TETRIS_HEIGHT as i32;
+                    match fade {
+                        None => textures.normal[*case as usize - 1].copy_to_canvas(canvas, x, y),
+                        Some(fade) => {
+                            let lock_time = tetris.lock_times[BUFFER_HEIGHT + line_nb][case_nb];
+                            let age = tetris.clock.saturating_sub(lock_time);
+                            if age < fade {
+                                let alpha = 255 - (255 * age.as_millis() / fade.as_millis()) as u8;
+                                let texture = &mut textures.fading[*case as usize - 1];
+                                texture.set_alpha(alpha);
+                                texture.copy_to_canvas(canvas, x, y);
+                            }
+                        }
+                    }
+                    if flash {
+                        textures.outline.copy_to_canvas(canvas, x, y);
+                    }
+                }
+            }
+            self.draw_garbage_meter(canvas, textures, game);
+        }
+        display_game_information(tetris, canvas, texture_creator, font,
+                                 next_x, self.y + BLOCK_Y - 120);
+        let white = Color::RGB(255, 255, 255);
+        match tetris.goal {
+            Goal::Lines(lines) => {
+                display_text(canvas, texture_creator, font,
+                             &format!("Time: {}", format_time(game.elapsed)), white,
+                             next_x, self.y + BLOCK_Y - 190);
+                display_text(canvas, texture_creator, font,
+                             &format!("Lines left: {}", lines.saturating_sub(tetris.nb_lines)),
+                             white, next_x, self.y + BLOCK_Y - 155);
+            }
+            Goal::Garbage(lines) => {
+                display_text(canvas, texture_creator, font,
+                             &format!("Time: {}", format_time(game.elapsed)), white,
+                             next_x, self.y + BLOCK_Y - 190);
+                display_text(canvas, texture_creator, font,
+                             &format!("Garbage left: {}",
+                                      lines.saturating_sub(tetris.garbage_cleared)),
+                             white, next_x, self.y + BLOCK_Y - 155);
+            }
+            Goal::Time(limit) => {
+                display_text(canvas, texture_creator, font,
+                             &format!("Time left: {}",
+                                      format_time(limit.saturating_sub(game.elapsed))),
+                             white, next_x, self.y + BLOCK_Y - 155);
+            }
+            Goal::None => {}
+        }
+    }
+
+    // One mark per incoming line from the bottom up, the oldest at the bottom
+    fn draw_garbage_meter(&self, canvas: &mut Canvas<Window>, textures: &PieceTextures,
+                          game: &Game) {
+        let x = self.x - METER_WIDTH as i32 - METER_GAP;
+        let max_lines = self.arena_height / TETRIS_HEIGHT;
+        let mut lines = 0;
+        for garbage in game.incoming.incoming.iter() {
+            let texture = if garbage.delay == Duration::from_millis(0) {
+                &textures.garbage_ready
+            } else {
+                &textures.garbage_waiting
+            };
+            for _ in 0..garbage.lines {
+                if lines == max_lines {
+                    return;
+                }
+                lines += 1;
+                let y = self.y + (self.arena_height - lines * TETRIS_HEIGHT) as i32;
+                texture.copy_to_canvas(canvas, x, y);
+            }
+        }
+    }
+
+    fn draw_in_arena(&self, canvas: &mut Canvas<Window>, textures: &[TextureGroup],
+                     piece: &Tetrimino) {
+        for (x, y, case) in piece.current_cells() {
+            // rows of the hidden buffer aren't drawn
+            let y = y - BUFFER_HEIGHT as isize;
+            if y < 0 {
+                continue;
+            }
+            textures[case as usize - 1].copy_to_canvas(
+                canvas,
+                self.x + x as i32 * TETRIS_HEIGHT as i32,
+                self.y + y as i32 * TETRIS_HEIGHT as i32);
+        }
+    }
+}
+
+fn draw_preview(canvas: &mut Canvas<Window>, textures: &[TextureGroup], size: u32,
+                piece: &Tetrimino, x: i32, y: i32) {
+    for (line_nb, line) in piece.states[piece.current_state
+        as usize].iter().enumerate() {
+        for (case_nb, case) in line.iter().enumerate() {
+            if *case == 0 {
+                continue;
+            }
+            textures[*case as usize - 1].copy_to_canvas(
+                canvas,
+                x + case_nb as i32 * size as i32,
+                y + line_nb as i32 * size as i32);
+        }
+    }
+}
+
+// A black rectangle with a white border around it
+fn framed_rect<'a>(canvas: &mut Canvas<Window>,
+                   texture_creator: &'a TextureCreator<WindowContext>,
+                   width: u32, height: u32) -> TextureGroup<'a> {
+    let mut group = TextureGroup::new();
+    group.add(create_texture_rect(
+        canvas,
+        texture_creator,
+        255, 255, 255,
+        width + 20,
+        height + 20,
+    ).expect("Failed to create a texture"), -10, -10);
+    group.add(create_texture_rect(
+        canvas,
+        texture_creator,
+        0, 0, 0,
+        width,
+        height,
+    ).expect("Failed to create a texture"), 0, 0);
+    group
+}
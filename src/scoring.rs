@@ -0,0 +1,314 @@
+use crate::tetrimino::{Action, Tetrimino, TetriminoKind};
+
+pub const SOFT_DROP_POINTS: u32 = 1;
+pub const HARD_DROP_POINTS: u32 = 2;
+const COMBO_POINTS: u32 = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// Corners of the T's 3x3 box, the first two being on the pointing side, per rotation state
+const T_CORNERS: [[(isize, isize); 4]; 4] = [
+    [(0, 0), (2, 0), (0, 2), (2, 2)],
+    [(2, 0), (2, 2), (0, 0), (0, 2)],
+    [(0, 2), (2, 2), (0, 0), (2, 0)],
+    [(0, 0), (0, 2), (2, 0), (2, 2)],
+];
+// the (+-1, -2) kick of quarter turns, which always makes a full T-spin
+const T_SPIN_TRIPLE_KICK: usize = 4;
+
+/*
+ * 3-corner rule: a T locked right after a rotation with 3 of its 4 corners
+ * blocked is a T-spin. It is a mini unless both corners on its pointing side
+ * are blocked or it got there with the T-spin triple kick.
+ */
+pub fn detect_t_spin(piece: &Tetrimino, game_map: &[Vec<u8>]) -> TSpin {
+    let (quarter_turns, kick) = match piece.last_action {
+        Action::Rotation { quarter_turns, kick } if piece.kind == TetriminoKind::T =>
+            (quarter_turns, kick),
+        _ => return TSpin::None,
+    };
+    // the 5th kick of the 180 table is a plain shift
+    let triple_kick = quarter_turns != 2 && kick == T_SPIN_TRIPLE_KICK;
+    let blocked: Vec<bool> = T_CORNERS[piece.current_state as usize]
+        .iter()
+        .map(|(corner_x, corner_y)| {
+            // top left map cell of the corner, corners are whole minos in big mode
+            let x = (piece.x + corner_x) * piece.scale as isize;
+            let y = (piece.y + corner_y) * piece.scale as isize;
+            y < 0 || x < 0 || y as usize >= game_map.len() ||
+                x as usize >= game_map[y as usize].len() ||
+                game_map[y as usize][x as usize] != 0
+        })
+        .collect();
+    if blocked.iter().filter(|corner| **corner).count() < 3 {
+        TSpin::None
+    } else if (blocked[0] && blocked[1]) || triple_kick {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+// What a single lock produced, before combo and back-to-back are applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LockResult {
+    pub lines: u32,
+    pub t_spin: TSpin,
+    pub perfect_clear: bool,
+}
+
+impl LockResult {
+    // Tetrises and line clearing T-spins keep the back-to-back chain alive
+    pub fn is_difficult(&self) -> bool {
+        self.lines == 4 || (self.lines > 0 && self.t_spin != TSpin::None)
+    }
+
+    fn base_points(&self) -> u32 {
+        match (self.t_spin, self.lines) {
+            (TSpin::None, 0) => 0,
+            (TSpin::None, 1) => 100,
+            (TSpin::None, 2) => 300,
+            (TSpin::None, 3) => 500,
+            (TSpin::None, _) => 800,
+            (TSpin::Mini, 0) => 100,
+            (TSpin::Mini, 1) => 200,
+            (TSpin::Mini, _) => 400,
+            (TSpin::Full, 0) => 400,
+            (TSpin::Full, 1) => 800,
+            (TSpin::Full, 2) => 1200,
+            (TSpin::Full, _) => 1600,
+        }
+    }
+
+    fn perfect_clear_points(&self, back_to_back: bool) -> u32 {
+        match self.lines {
+            0 => 0,
+            1 => 800,
+            2 => 1200,
+            3 => 1800,
+            _ if back_to_back => 3200,
+            _ => 2000,
+        }
+    }
+
+    pub fn name(&self) -> Option<String> {
+        let lines = match self.lines {
+            0 => "",
+            1 => "Single",
+            2 => "Double",
+            3 => "Triple",
+            _ => "Tetris",
+        };
+        let name = match self.t_spin {
+            TSpin::None => lines.to_string(),
+            TSpin::Mini => format!("T-Spin Mini {}", lines),
+            TSpin::Full => format!("T-Spin {}", lines),
+        };
+        let name = name.trim_end().to_string();
+        if name.is_empty() {
+            None
+        } else {
+            Some(name)
+        }
+    }
+}
+
+// Guideline scoring: line clears, T-spins, combos, back-to-back and perfect clears
+pub struct Scoring {
+    // number of consecutive line clearing locks minus one, -1 when no combo is running
+    pub combo: i32,
+    pub max_combo: u32,
+    pub back_to_back: bool,
+    pub last_lock: Option<LockResult>,
+    // whether the last lock got the back-to-back bonus
+    pub last_back_to_back: bool,
+}
+
+impl Scoring {
+    pub fn new() -> Scoring {
+        Scoring {
+            combo: -1,
+            max_combo: 0,
+            back_to_back: false,
+            last_lock: None,
+            last_back_to_back: false,
+        }
+    }
+
+    // Updates the chains and returns the points earned by this lock
+    pub fn on_lock(&mut self, result: LockResult, level: u32) -> u32 {
+        self.last_lock = Some(result);
+        self.last_back_to_back = false;
+        let mut points = result.base_points();
+
+        if result.lines == 0 {
+            self.combo = -1;
+            return points * level;
+        }
+
+        let back_to_back = self.back_to_back && result.is_difficult();
+        self.last_back_to_back = back_to_back;
+        if back_to_back {
+            points += points / 2;
+        }
+        self.back_to_back = result.is_difficult();
+
+        self.combo += 1;
+        if self.combo > 0 {
+            points += COMBO_POINTS * self.combo as u32;
+            self.max_combo = self.max_combo.max(self.combo as u32);
+        }
+
+        if result.perfect_clear {
+            points += result.perfect_clear_points(back_to_back);
+        }
+        points * level
+    }
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetrimino::create_tetrimino;
+
+    fn clear(lines: u32) -> LockResult {
+        LockResult { lines, t_spin: TSpin::None, perfect_clear: false }
+    }
+
+    fn t_spin(lines: u32, t_spin: TSpin) -> LockResult {
+        LockResult { lines, t_spin, perfect_clear: false }
+    }
+
+    #[test]
+    fn points_scale_with_level() {
+        let points: Vec<u32> = (0..=4)
+            .map(|lines| Scoring::new().on_lock(clear(lines), 3))
+            .collect();
+        assert_eq!(points, vec![0, 300, 900, 1500, 2400]);
+        assert_eq!(Scoring::new().on_lock(t_spin(0, TSpin::Mini), 1), 100);
+        assert_eq!(Scoring::new().on_lock(t_spin(1, TSpin::Mini), 1), 200);
+        assert_eq!(Scoring::new().on_lock(t_spin(0, TSpin::Full), 1), 400);
+        assert_eq!(Scoring::new().on_lock(t_spin(2, TSpin::Full), 1), 1200);
+        assert_eq!(Scoring::new().on_lock(t_spin(3, TSpin::Full), 1), 1600);
+    }
+
+    #[test]
+    fn combo_grows_until_a_lock_clears_nothing() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.on_lock(clear(1), 1), 100);
+        assert_eq!(scoring.on_lock(clear(1), 1), 150);
+        assert_eq!(scoring.on_lock(clear(1), 1), 200);
+        assert_eq!(scoring.combo, 2);
+        assert_eq!(scoring.on_lock(clear(0), 1), 0);
+        assert_eq!(scoring.combo, -1);
+        assert_eq!(scoring.max_combo, 2);
+        assert_eq!(scoring.on_lock(clear(1), 1), 100);
+    }
+
+    #[test]
+    fn back_to_back_needs_consecutive_difficult_clears() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.on_lock(clear(4), 1), 800);
+        // locks without lines don't break the chain
+        scoring.on_lock(clear(0), 1);
+        assert_eq!(scoring.on_lock(t_spin(2, TSpin::Full), 1), 1800);
+        assert!(scoring.last_back_to_back);
+        scoring.on_lock(clear(0), 1);
+        assert_eq!(scoring.on_lock(clear(1), 1), 100);
+        assert!(!scoring.last_back_to_back);
+        scoring.on_lock(clear(0), 1);
+        assert_eq!(scoring.on_lock(clear(4), 1), 800);
+    }
+
+    #[test]
+    fn perfect_clear_bonus() {
+        let single = LockResult { perfect_clear: true, ..clear(1) };
+        assert_eq!(Scoring::new().on_lock(single, 1), 900);
+        let tetris = LockResult { perfect_clear: true, ..clear(4) };
+        assert_eq!(Scoring::new().on_lock(tetris, 2), 5600);
+        let mut scoring = Scoring::new();
+        scoring.on_lock(clear(4), 1);
+        scoring.on_lock(clear(0), 1);
+        assert_eq!(scoring.on_lock(tetris, 1), 1200 + 3200);
+    }
+
+    #[test]
+    fn lock_names() {
+        assert_eq!(clear(0).name(), None);
+        assert_eq!(clear(4).name().as_deref(), Some("Tetris"));
+        assert_eq!(t_spin(0, TSpin::Full).name().as_deref(), Some("T-Spin"));
+        assert_eq!(t_spin(1, TSpin::Mini).name().as_deref(), Some("T-Spin Mini Single"));
+    }
+
+    // a plain clockwise rotation
+    const ROTATION: Action = Action::Rotation { quarter_turns: 1, kick: 0 };
+
+    // A T pointing up at (3, 3), its corners are (3, 3) and (5, 3) in front, (3, 5) and (5, 5)
+    fn t_with_corners(corners: &[(usize, usize)],
+                      last_action: Action) -> (Tetrimino, Vec<Vec<u8>>) {
+        let mut piece = create_tetrimino(TetriminoKind::T);
+        piece.x = 3;
+        piece.y = 3;
+        piece.last_action = last_action;
+        let mut map = vec![vec![0; 10]; 8];
+        for (x, y) in corners {
+            map[*y][*x] = 1;
+        }
+        (piece, map)
+    }
+
+    #[test]
+    fn t_spin_full_and_mini() {
+        let (piece, map) = t_with_corners(&[(3, 3), (5, 3), (3, 5)], ROTATION);
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Full);
+        let (piece, map) = t_with_corners(&[(3, 3), (3, 5), (5, 5)], ROTATION);
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Mini);
+        let (piece, map) = t_with_corners(&[(3, 3), (3, 5)], ROTATION);
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::None);
+    }
+
+    #[test]
+    fn t_spin_needs_a_rotated_t() {
+        let (piece, map) = t_with_corners(&[(3, 3), (5, 3), (3, 5)], Action::Move);
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::None);
+        let (piece, map) = t_with_corners(&[(3, 3), (5, 3), (3, 5)], ROTATION);
+        let piece = Tetrimino { kind: TetriminoKind::S, ..piece };
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::None);
+    }
+
+    #[test]
+    fn triple_kick_upgrades_minis_for_quarter_turns_only() {
+        let corners = [(3, 3), (3, 5), (5, 5)];
+        let (piece, map) = t_with_corners(&corners, Action::Rotation { quarter_turns: 3, kick: 4 });
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Full);
+        // the 5th 180 kick is a sideways shift
+        let (piece, map) = t_with_corners(&corners, Action::Rotation { quarter_turns: 2, kick: 4 });
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Mini);
+    }
+
+    #[test]
+    fn t_spin_corners_outside_the_map_are_blocked() {
+        let mut piece = create_tetrimino(TetriminoKind::T);
+        piece.x = -1;
+        piece.y = 3;
+        piece.current_state = 1;
+        piece.last_action = ROTATION;
+        let mut map = vec![vec![0; 10]; 8];
+        // pointing right, (1, 3) and (1, 5) are the front corners
+        map[3][1] = 1;
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Mini);
+        map[5][1] = 1;
+        assert_eq!(detect_t_spin(&piece, &map), TSpin::Full);
+    }
+}
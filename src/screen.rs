@@ -0,0 +1,114 @@
+use crate::event::MenuAction;
+use std::time::Duration;
+use tetris::{GameConfig, GameMode, Handling, RandomizerKind};
+use tetris::tetris::MAX_NEXT_COUNT;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Screen {
+    Title,
+    ModeSelect,
+    Settings,
+    InGame,
+    GameOver,
+    HighScores,
+}
+
+pub const TITLE_ITEMS: [&str; 4] = ["Play", "Settings", "High scores", "Quit"];
+pub const RESULT_ITEMS: [&str; 2] = ["Retry", "Main menu"];
+
+// Records a finished game beat, shown on the results screen
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Records {
+    pub score: bool,
+    pub lines: bool,
+    pub time: bool,
+}
+
+// Like 1:05.250
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
+
+// Moves the selection of a menu with `len` items, wrapping around
+pub fn navigate(selected: &mut usize, len: usize, action: MenuAction) {
+    match action {
+        MenuAction::Up => *selected = (*selected + len - 1) % len,
+        MenuAction::Down => *selected = (*selected + 1) % len,
+        _ => {}
+    }
+}
+
+// Everything the settings screen can change, used for every new game
+pub struct Settings {
+    pub config: GameConfig,
+    pub handling: Handling,
+    // fixed seed from the command line, a fresh one is picked otherwise
+    pub seed: Option<u64>,
+}
+
+impl Settings {
+    pub const LEN: usize = 11;
+
+    pub fn items(&self) -> Vec<String> {
+        vec![
+            format!("Randomizer:  {}", self.config.randomizer.name()),
+            format!("Next pieces: {}", self.config.next_count),
+            format!("Width:       {}", self.config.width),
+            format!("Height:      {}", self.config.height),
+            format!("DAS:         {} ms", self.handling.das.as_millis()),
+            format!("ARR:         {} ms", self.handling.arr.as_millis()),
+            match self.handling.soft_drop_factor {
+                0 => "Soft drop:   instant".to_string(),
+                factor => format!("Soft drop:   x{}", factor),
+            },
+            format!("Ultra time:  {}:{:02}", self.config.time_limit.as_secs() / 60,
+                    self.config.time_limit.as_secs() % 60),
+            format!("Dig lines:   {}", self.config.dig_lines),
+            format!("Fade time:   {} s", self.config.fade_time.as_secs()),
+            format!("Garbage delay: {} ms", self.config.garbage_delay.as_millis()),
+        ]
+    }
+
+    // Changes the value of item `index` by one step in the direction of `delta`
+    pub fn adjust(&mut self, index: usize, delta: i64) {
+        match index {
+            0 => {
+                let kinds = RandomizerKind::ALL;
+                let current = kinds.iter().position(|kind| *kind == self.config.randomizer)
+                    .unwrap_or(0);
+                let next = (current as i64 + delta).rem_euclid(kinds.len() as i64);
+                self.config.randomizer = kinds[next as usize];
+            }
+            1 => self.config.next_count = step(self.config.next_count as i64, delta, 1,
+                                               MAX_NEXT_COUNT as i64) as usize,
+            2 => self.config.width = step(self.config.width as i64, delta, 4, 20) as usize,
+            3 => self.config.height = step(self.config.height as i64, delta, 4, 22) as usize,
+            4 => self.handling.das = step_millis(self.handling.das, delta * 10, 0, 500),
+            5 => self.handling.arr = step_millis(self.handling.arr, delta * 5, 0, 200),
+            6 => self.handling.soft_drop_factor = step(self.handling.soft_drop_factor as i64,
+                                                       delta, 0, 40) as u32,
+            7 => self.config.time_limit = step_millis(self.config.time_limit, delta * 30_000,
+                                                      30_000, 600_000),
+            8 => self.config.dig_lines = step(self.config.dig_lines as i64, delta * 10,
+                                              10, 100) as u32,
+            9 => self.config.fade_time = step_millis(self.config.fade_time, delta * 1000,
+                                                     1000, 10_000),
+            10 => self.config.garbage_delay = step_millis(self.config.garbage_delay,
+                                                          delta * 100, 0, 2000),
+            _ => {}
+        }
+    }
+
+    pub fn config_for(&self, mode: GameMode) -> GameConfig {
+        GameConfig { mode, ..self.config }
+    }
+}
+
+fn step(value: i64, delta: i64, min: i64, max: i64) -> i64 {
+    (value + delta).clamp(min, max)
+}
+
+fn step_millis(value: Duration, delta: i64, min: i64, max: i64) -> Duration {
+    Duration::from_millis(step(value.as_millis() as i64, delta, min, max) as u64)
+}
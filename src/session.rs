@@ -0,0 +1,85 @@
+use crate::game::{Game, GameConfig, Input};
+use std::time::Duration;
+
+/*
+ * One or more games played side by side. With several players the lines
+ * sent by each of them come out as garbage on the boards of the others,
+ * and the last one still standing wins.
+ */
+pub struct Session {
+    pub games: Vec<Game>,
+    // index of the winning game once a versus session is decided
+    pub winner: Option<usize>,
+}
+
+impl Session {
+    // Every player gets the same seed, and so the same pieces
+    pub fn new(config: GameConfig, seed: u64, players: usize) -> Session {
+        Session {
+            games: (0..players.max(1)).map(|_| Game::with_config(config, seed)).collect(),
+            winner: None,
+        }
+    }
+
+    pub fn is_versus(&self) -> bool {
+        self.games.len() > 1
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.games.iter().all(|game| game.over)
+    }
+
+    pub fn paused(&self) -> bool {
+        self.games.iter().any(|game| game.paused)
+    }
+
+    pub fn pause(&mut self) {
+        for game in self.games.iter_mut() {
+            game.pause();
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        let paused = !self.paused();
+        for game in self.games.iter_mut() {
+            game.paused = paused;
+        }
+    }
+
+    // Steps every game with its own inputs, then sends garbage between them
+    pub fn step(&mut self, inputs: &[Vec<Input>], dt: Duration) {
+        for (index, game) in self.games.iter_mut().enumerate() {
+            let inputs = inputs.get(index).map_or(&[][..], |inputs| &inputs[..]);
+            game.step(inputs, dt);
+        }
+        self.exchange_garbage();
+        self.check_winner();
+    }
+
+    fn exchange_garbage(&mut self) {
+        for sender in 0..self.games.len() {
+            let lines = self.games[sender].take_attack();
+            if lines == 0 {
+                continue;
+            }
+            for (receiver, game) in self.games.iter_mut().enumerate() {
+                if receiver != sender {
+                    game.receive_garbage(lines);
+                }
+            }
+        }
+    }
+
+    fn check_winner(&mut self) {
+        if !self.is_versus() || self.winner.is_some() {
+            return;
+        }
+        let running: Vec<usize> = (0..self.games.len())
+            .filter(|index| !self.games[*index].over)
+            .collect();
+        if running.len() == 1 {
+            self.winner = Some(running[0]);
+            self.games[running[0]].finish();
+        }
+    }
+}
@@ -0,0 +1,44 @@
+/*
+ * SRS wall kick tables, indexed by the starting rotation state
+ * (0 = spawn, 1 = R, 2 = 2, 3 = L). Offsets are (x, y) with y pointing
+ * up as in the guideline, so they have to be negated on our map.
+ */
+pub type Kicks = &'static [(isize, isize)];
+
+pub const JLSTZ_CLOCKWISE: [Kicks; 4] = [
+    &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 0 -> R
+    &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 2
+    &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 2 -> L
+    &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 0
+];
+
+pub const JLSTZ_COUNTER_CLOCKWISE: [Kicks; 4] = [
+    &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],    // 0 -> L
+    &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],     // R -> 0
+    &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)], // 2 -> R
+    &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],  // L -> 2
+];
+
+pub const I_CLOCKWISE: [Kicks; 4] = [
+    &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // 0 -> R
+    &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // R -> 2
+    &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // 2 -> L
+    &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // L -> 0
+];
+
+pub const I_COUNTER_CLOCKWISE: [Kicks; 4] = [
+    &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)], // 0 -> L
+    &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)], // R -> 0
+    &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)], // 2 -> R
+    &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)], // L -> 2
+];
+
+// 180 kicks are not part of the guideline, this is the widely used SRS+ table
+pub const HALF_TURN: [Kicks; 4] = [
+    &[(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],    // 0 -> 2
+    &[(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],      // R -> L
+    &[(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)], // 2 -> 0
+    &[(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],   // L -> R
+];
+
+pub const O_KICKS: Kicks = &[(0, 0)];
@@ -1,8 +1,7 @@
 mod shape_impl_data;
+mod kick_data;
 
 
-extern crate rand;
-
 /*
  * shape of the tetrimino
  * for example
@@ -16,34 +15,59 @@ extern crate rand;
  */
 type Piece = Vec<Vec<u8>>;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TetriminoKind {
+    I,
+    J,
+    L,
+    O,
+    S,
+    Z,
+    T,
+}
+
+impl TetriminoKind {
+    pub const ALL: [TetriminoKind; 7] = [
+        TetriminoKind::I, TetriminoKind::J, TetriminoKind::L, TetriminoKind::O,
+        TetriminoKind::S, TetriminoKind::Z, TetriminoKind::T,
+    ];
+}
+
+// Last thing that successfully moved the piece, needed to recognize T-spins
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Move,
+    // clockwise quarter turns (1, 2 or 3) and index of the kick test that succeeded,
+    // 0 being the plain rotation
+    Rotation { quarter_turns: u8, kick: usize },
+}
+
 #[derive(Clone)]
 pub struct Tetrimino {
+    pub kind: TetriminoKind,
     pub states: Vec<Piece>,
     pub x: isize,
-    pub y: usize,
+    pub y: isize,
     pub current_state: u8,
+    pub last_action: Action,
+    // map cells per side of a mino, 2 in big mode where x and y count big cells
+    pub scale: usize,
 }
 
 trait TetriminoGenerator {
-    fn new() -> Tetrimino;
+    fn generate() -> Tetrimino;
 }
 
-pub fn create_new_tetrimino() -> Tetrimino {
-    static mut PREV: u8 = 7;
-    let mut rand_nb = rand::random::<u8>() % 7;
-    if unsafe { PREV } == rand_nb {
-        rand_nb = rand::random::<u8>() % 7;
-    }
-    unsafe { PREV = rand_nb; }
-    match rand_nb {
-        0 => shape_impl_data::TetriminoI::new(),
-        1 => shape_impl_data::TetriminoJ::new(),
-        2 => shape_impl_data::TetriminoL::new(),
-        3 => shape_impl_data::TetriminoO::new(),
-        4 => shape_impl_data::TetriminoS::new(),
-        5 => shape_impl_data::TetriminoZ::new(),
-        6 => shape_impl_data::TetriminoT::new(),
-        _ => unreachable!(),
+pub fn create_tetrimino(kind: TetriminoKind) -> Tetrimino {
+    match kind {
+        TetriminoKind::I => shape_impl_data::TetriminoI::generate(),
+        TetriminoKind::J => shape_impl_data::TetriminoJ::generate(),
+        TetriminoKind::L => shape_impl_data::TetriminoL::generate(),
+        TetriminoKind::O => shape_impl_data::TetriminoO::generate(),
+        TetriminoKind::S => shape_impl_data::TetriminoS::generate(),
+        TetriminoKind::Z => shape_impl_data::TetriminoZ::generate(),
+        TetriminoKind::T => shape_impl_data::TetriminoT::generate(),
     }
 }
 
@@ -54,47 +78,181 @@ impl Tetrimino {
     }
 
     pub fn test_position(&self, game_map: &[Vec<u8>],
-                     tmp_state: usize, x: isize, y: usize) -> bool {
-        for decal_y in 0..4 {
-            for decal_x in 0..4 {
-                let x = x + decal_x;
-                if self.states[tmp_state][decal_y][decal_x as usize] != 0
-                    &&
-                    (y + decal_y >= game_map.len() ||
-                        x < 0 ||
-                        x as usize >= game_map[y + decal_y].len() ||
-                        game_map[y + decal_y][x as usize] != 0) {
-                    return false;
+                         tmp_state: usize, x: isize, y: isize) -> bool {
+        self.cells(tmp_state, x, y).iter().all(|(x, y, _)| {
+            *y >= 0 && (*y as usize) < game_map.len() &&
+                *x >= 0 && (*x as usize) < game_map[*y as usize].len() &&
+                game_map[*y as usize][*x as usize] == 0
+        })
+    }
+
+    // Map cells covered by the piece in state `tmp_state` at (x, y), with their value
+    pub fn cells(&self, tmp_state: usize, x: isize, y: isize) -> Vec<(isize, isize, u8)> {
+        let scale = self.scale as isize;
+        let mut cells = Vec::new();
+        for (decal_y, line) in self.states[tmp_state].iter().enumerate() {
+            for (decal_x, case) in line.iter().enumerate() {
+                if *case == 0 {
+                    continue;
+                }
+                for big_y in 0..scale {
+                    for big_x in 0..scale {
+                        cells.push(((x + decal_x as isize) * scale + big_x,
+                                    (y + decal_y as isize) * scale + big_y,
+                                    *case));
+                    }
                 }
             }
         }
-        true
+        cells
     }
 
-    pub fn rotate(&mut self, game_map: &[Vec<u8>]) {
-        let mut tmp_state = self.current_state + 1;
-        if tmp_state as usize >= self.states.len() {
-            tmp_state = 0;
-        }
-        let x_pos = [0, -1, 1, -2, 2, -3];
-        for x in x_pos.iter() {
+    pub fn current_cells(&self) -> Vec<(isize, isize, u8)> {
+        self.cells(self.current_state as usize, self.x, self.y)
+    }
+
+    pub fn rotate(&mut self, game_map: &[Vec<u8>]) -> bool {
+        let kicks = match self.kind {
+            TetriminoKind::I => kick_data::I_CLOCKWISE[self.current_state as usize],
+            TetriminoKind::O => kick_data::O_KICKS,
+            _ => kick_data::JLSTZ_CLOCKWISE[self.current_state as usize],
+        };
+        self.rotate_with_kicks(game_map, 1, kicks)
+    }
+
+    pub fn rotate_left(&mut self, game_map: &[Vec<u8>]) -> bool {
+        let kicks = match self.kind {
+            TetriminoKind::I => kick_data::I_COUNTER_CLOCKWISE[self.current_state as usize],
+            TetriminoKind::O => kick_data::O_KICKS,
+            _ => kick_data::JLSTZ_COUNTER_CLOCKWISE[self.current_state as usize],
+        };
+        self.rotate_with_kicks(game_map, 3, kicks)
+    }
+
+    pub fn rotate_180(&mut self, game_map: &[Vec<u8>]) -> bool {
+        let kicks = match self.kind {
+            TetriminoKind::O => kick_data::O_KICKS,
+            _ => kick_data::HALF_TURN[self.current_state as usize],
+        };
+        self.rotate_with_kicks(game_map, 2, kicks)
+    }
+
+    // SRS rotation: tries each kick of the table in order, keeps the first that fits
+    fn rotate_with_kicks(&mut self, game_map: &[Vec<u8>], quarter_turns: u8,
+                         kicks: kick_data::Kicks) -> bool {
+        let tmp_state = (self.current_state + quarter_turns) % self.states.len() as u8;
+        for (kick, (kick_x, kick_y)) in kicks.iter().enumerate() {
             if self.test_position(game_map, tmp_state as usize,
-                                  self.x + x, self.y) == true {
+                                  self.x + kick_x, self.y - kick_y) {
                 self.current_state = tmp_state;
-                self.x += *x;
-                break;
+                self.x += kick_x;
+                self.y -= kick_y;
+                self.last_action = Action::Rotation { quarter_turns, kick };
+                return true;
             }
         }
+        false
     }
 
-    pub fn change_position(&mut self, game_map: &[Vec<u8>], new_x: isize, new_y: usize) -> bool {
+    pub fn change_position(&mut self, game_map: &[Vec<u8>], new_x: isize, new_y: isize) -> bool {
         if self.test_position(game_map, self.current_state as usize,
-                              new_x, new_y) == true {
-            self.x = new_x as isize;
+                              new_x, new_y) {
+            self.x = new_x;
             self.y = new_y;
+            self.last_action = Action::Move;
             true
         } else {
             false
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::shape_impl_data::{TetriminoI, TetriminoO, TetriminoT};
+
+    fn empty_map() -> Vec<Vec<u8>> {
+        vec![vec![0; 10]; 20]
+    }
+
+    fn placed(mut piece: Tetrimino, state: u8, x: isize, y: isize) -> Tetrimino {
+        piece.current_state = state;
+        piece.x = x;
+        piece.y = y;
+        piece
+    }
+
+    #[test]
+    fn rotation_without_kick() {
+        let mut piece = placed(TetriminoT::generate(), 0, 3, 5);
+        assert!(piece.rotate(&empty_map()));
+        assert_eq!((piece.current_state, piece.x, piece.y), (1, 3, 5));
+        assert_eq!(piece.last_action, Action::Rotation { quarter_turns: 1, kick: 0 });
+    }
+
+    #[test]
+    fn jlstz_kick_off_the_wall() {
+        // R -> 2 against the left wall takes the (+1, 0) kick
+        let mut piece = placed(TetriminoT::generate(), 1, -1, 5);
+        assert!(piece.rotate(&empty_map()));
+        assert_eq!((piece.current_state, piece.x, piece.y), (2, 0, 5));
+        assert_eq!(piece.last_action, Action::Rotation { quarter_turns: 1, kick: 1 });
+    }
+
+    #[test]
+    fn i_kick_off_the_floor() {
+        // a flat I in the bottom left corner can only stand up with the (+1, +2) kick
+        let mut piece = placed(TetriminoI::generate(), 0, 0, 18);
+        assert!(piece.rotate(&empty_map()));
+        assert_eq!((piece.current_state, piece.x, piece.y), (1, 1, 16));
+        assert_eq!(piece.last_action, Action::Rotation { quarter_turns: 1, kick: 4 });
+    }
+
+    #[test]
+    fn half_turn_kick_off_the_floor() {
+        let mut piece = placed(TetriminoT::generate(), 0, 3, 18);
+        assert!(piece.rotate_180(&empty_map()));
+        assert_eq!((piece.current_state, piece.x, piece.y), (2, 3, 17));
+        assert_eq!(piece.last_action, Action::Rotation { quarter_turns: 2, kick: 1 });
+    }
+
+    #[test]
+    fn counter_clockwise_uses_its_own_table() {
+        // the plain rotation and the (+1, 0) kick are blocked below, (+1, +1) fits
+        let mut map = empty_map();
+        map[7][4] = 1;
+        map[7][5] = 1;
+        let mut piece = placed(TetriminoT::generate(), 0, 3, 5);
+        assert!(piece.rotate_left(&map));
+        assert_eq!((piece.current_state, piece.x, piece.y), (3, 4, 4));
+        assert_eq!(piece.last_action, Action::Rotation { quarter_turns: 3, kick: 2 });
+    }
+
+    #[test]
+    fn rotation_fails_when_every_kick_is_blocked() {
+        let mut piece = placed(TetriminoT::generate(), 0, 3, 5);
+        let mut map = vec![vec![1; 10]; 20];
+        for (y, line) in piece.states[0].iter().enumerate() {
+            for (x, case) in line.iter().enumerate() {
+                if *case != 0 {
+                    map[5 + y][3 + x] = 0;
+                }
+            }
+        }
+        assert!(!piece.rotate(&map));
+        assert!(!piece.rotate_left(&map));
+        assert!(!piece.rotate_180(&map));
+        assert_eq!((piece.current_state, piece.x, piece.y), (0, 3, 5));
+        assert_eq!(piece.last_action, Action::None);
+    }
+
+    #[test]
+    fn big_pieces_cover_two_by_two_cells() {
+        let mut piece = placed(TetriminoO::generate(), 0, 1, 1);
+        piece.scale = 2;
+        let cells = piece.current_cells();
+        assert_eq!(cells.len(), 16);
+        assert!(cells.iter().all(|(x, y, _)| (4..8).contains(x) && (2..6).contains(y)));
+    }
+}
@@ -1,25 +1,38 @@
-use crate::tetrimino::{TetriminoGenerator, Tetrimino};
+use crate::tetrimino::{Action, TetriminoGenerator, TetriminoKind, Tetrimino};
 
 pub struct TetriminoI;
 
 impl TetriminoGenerator for TetriminoI {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::I,
             states: vec![
-                // -
-                vec![vec![1, 1, 1, 1],
+                // spawn
+                vec![vec![0, 0, 0, 0],
+                     vec![1, 1, 1, 1],
                      vec![0, 0, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // R
+                vec![vec![0, 0, 1, 0],
+                     vec![0, 0, 1, 0],
+                     vec![0, 0, 1, 0],
+                     vec![0, 0, 1, 0]],
+                // 2
+                vec![vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0],
+                     vec![1, 1, 1, 1],
                      vec![0, 0, 0, 0]],
-                // |
+                // L
                 vec![vec![0, 1, 0, 0],
                      vec![0, 1, 0, 0],
                      vec![0, 1, 0, 0],
                      vec![0, 1, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -27,31 +40,36 @@ impl TetriminoGenerator for TetriminoI {
 pub struct TetriminoJ;
 
 impl TetriminoGenerator for TetriminoJ {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::J,
             states: vec![
-                vec![vec![2, 2, 2, 0],
-                     vec![2, 0, 0, 0],
+                // spawn
+                vec![vec![2, 0, 0, 0],
+                     vec![2, 2, 2, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]],
-                // ⅂
-                vec![vec![2, 2, 0, 0],
+                // R
+                vec![vec![0, 2, 2, 0],
                      vec![0, 2, 0, 0],
                      vec![0, 2, 0, 0],
                      vec![0, 0, 0, 0]],
-                vec![vec![0, 0, 2, 0],
+                // 2
+                vec![vec![0, 0, 0, 0],
                      vec![2, 2, 2, 0],
-                     vec![0, 0, 0, 0],
+                     vec![0, 0, 2, 0],
                      vec![0, 0, 0, 0]],
                 // L
-                vec![vec![2, 0, 0, 0],
-                     vec![2, 0, 0, 0],
+                vec![vec![0, 2, 0, 0],
+                     vec![0, 2, 0, 0],
                      vec![2, 2, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -59,30 +77,36 @@ impl TetriminoGenerator for TetriminoJ {
 pub struct TetriminoL;
 
 impl TetriminoGenerator for TetriminoL {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::L,
             states: vec![
-                vec![vec![3, 3, 3, 0],
-                     vec![0, 0, 3, 0],
+                // spawn
+                vec![vec![0, 0, 3, 0],
+                     vec![3, 3, 3, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]],
-                // ⅃
+                // R
                 vec![vec![0, 3, 0, 0],
                      vec![0, 3, 0, 0],
-                     vec![3, 3, 0, 0],
+                     vec![0, 3, 3, 0],
                      vec![0, 0, 0, 0]],
-                vec![vec![3, 0, 0, 0],
+                // 2
+                vec![vec![0, 0, 0, 0],
                      vec![3, 3, 3, 0],
-                     vec![0, 0, 0, 0],
+                     vec![3, 0, 0, 0],
                      vec![0, 0, 0, 0]],
+                // L
                 vec![vec![3, 3, 0, 0],
-                     vec![3, 0, 0, 0],
-                     vec![3, 0, 0, 0],
+                     vec![0, 3, 0, 0],
+                     vec![0, 3, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -90,18 +114,36 @@ impl TetriminoGenerator for TetriminoL {
 pub struct TetriminoO;
 
 impl TetriminoGenerator for TetriminoO {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::O,
             states: vec![
-                // 口
-                vec![vec![4, 4, 0, 0],
-                     vec![4, 4, 0, 0],
+                // spawn
+                vec![vec![0, 4, 4, 0],
+                     vec![0, 4, 4, 0],
+                     vec![0, 0, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // R
+                vec![vec![0, 4, 4, 0],
+                     vec![0, 4, 4, 0],
+                     vec![0, 0, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // 2
+                vec![vec![0, 4, 4, 0],
+                     vec![0, 4, 4, 0],
+                     vec![0, 0, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // L
+                vec![vec![0, 4, 4, 0],
+                     vec![0, 4, 4, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 5,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -109,21 +151,36 @@ impl TetriminoGenerator for TetriminoO {
 pub struct TetriminoS;
 
 impl TetriminoGenerator for TetriminoS {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::S,
             states: vec![
+                // spawn
                 vec![vec![0, 5, 5, 0],
                      vec![5, 5, 0, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]],
+                // R
                 vec![vec![0, 5, 0, 0],
                      vec![0, 5, 5, 0],
                      vec![0, 0, 5, 0],
+                     vec![0, 0, 0, 0]],
+                // 2
+                vec![vec![0, 0, 0, 0],
+                     vec![0, 5, 5, 0],
+                     vec![5, 5, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // L
+                vec![vec![5, 0, 0, 0],
+                     vec![5, 5, 0, 0],
+                     vec![0, 5, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -131,21 +188,36 @@ impl TetriminoGenerator for TetriminoS {
 pub struct TetriminoZ;
 
 impl TetriminoGenerator for TetriminoZ {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::Z,
             states: vec![
+                // spawn
                 vec![vec![6, 6, 0, 0],
                      vec![0, 6, 6, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]],
+                // R
                 vec![vec![0, 0, 6, 0],
                      vec![0, 6, 6, 0],
                      vec![0, 6, 0, 0],
+                     vec![0, 0, 0, 0]],
+                // 2
+                vec![vec![0, 0, 0, 0],
+                     vec![6, 6, 0, 0],
+                     vec![0, 6, 6, 0],
+                     vec![0, 0, 0, 0]],
+                // L
+                vec![vec![0, 6, 0, 0],
+                     vec![6, 6, 0, 0],
+                     vec![6, 0, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
@@ -153,29 +225,36 @@ impl TetriminoGenerator for TetriminoZ {
 pub struct TetriminoT;
 
 impl TetriminoGenerator for TetriminoT {
-    fn new() -> Tetrimino {
+    fn generate() -> Tetrimino {
         Tetrimino {
+            kind: TetriminoKind::T,
             states: vec![
-                vec![vec![7, 7, 7, 0],
-                     vec![0, 7, 0, 0],
+                // spawn
+                vec![vec![0, 7, 0, 0],
+                     vec![7, 7, 7, 0],
                      vec![0, 0, 0, 0],
                      vec![0, 0, 0, 0]],
+                // R
                 vec![vec![0, 7, 0, 0],
-                     vec![7, 7, 0, 0],
+                     vec![0, 7, 7, 0],
                      vec![0, 7, 0, 0],
                      vec![0, 0, 0, 0]],
-                vec![vec![0, 7, 0, 0],
+                // 2
+                vec![vec![0, 0, 0, 0],
                      vec![7, 7, 7, 0],
-                     vec![0, 0, 0, 0],
+                     vec![0, 7, 0, 0],
                      vec![0, 0, 0, 0]],
+                // L
                 vec![vec![0, 7, 0, 0],
-                     vec![0, 7, 7, 0],
+                     vec![7, 7, 0, 0],
                      vec![0, 7, 0, 0],
                      vec![0, 0, 0, 0]]
             ],
-            x: 4,
+            x: 3,
             y: 0,
             current_state: 0,
+            last_action: Action::None,
+            scale: 1,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+/*
+ * Level curves: the speed of the game at each level. Gravity is in cells
+ * per frame at 60 frames per second, so 1.0 drops a row every frame and
+ * 20.0 (20G) drops a piece to the floor as soon as it appears.
+ */
+pub struct Level {
+    // lines to clear before moving on to the next level
+    pub lines: u32,
+    pub gravity: f64,
+    // delay between a piece locking and the next one spawning
+    pub are: Duration,
+    // None keeps the lock delay the game was configured with
+    pub lock_delay: Option<Duration>,
+}
+
+pub type LevelCurve = &'static [Level];
+
+pub const FRAME_MILLIS: f64 = 1000. / 60.;
+pub const INSTANT_GRAVITY: f64 = 20.;
+
+// A level dropping one row every `millis` milliseconds
+const fn timed(lines: u32, millis: f64) -> Level {
+    Level {
+        lines,
+        gravity: FRAME_MILLIS / millis,
+        are: Duration::from_millis(0),
+        lock_delay: None,
+    }
+}
+
+const fn master(gravity: f64, are: u64, lock_delay: u64) -> Level {
+    Level {
+        lines: 10,
+        gravity,
+        are: Duration::from_millis(are),
+        lock_delay: Some(Duration::from_millis(lock_delay)),
+    }
+}
+
+pub const MARATHON_CURVE: LevelCurve = &[
+    timed(20, 1000.), timed(20, 850.), timed(20, 700.), timed(20, 600.), timed(20, 500.),
+    timed(20, 400.), timed(20, 300.), timed(20, 250.), timed(20, 221.), timed(20, 190.),
+];
+
+// reaches 20G quickly, then keeps shortening ARE and lock delay
+pub const MASTER_CURVE: LevelCurve = &[
+    master(1. / 16., 400, 500),
+    master(1. / 8., 400, 500),
+    master(1. / 4., 400, 500),
+    master(1. / 2., 400, 500),
+    master(1., 400, 500),
+    master(2., 400, 500),
+    master(5., 400, 500),
+    master(INSTANT_GRAVITY, 400, 500),
+    master(INSTANT_GRAVITY, 350, 450),
+    master(INSTANT_GRAVITY, 300, 400),
+    master(INSTANT_GRAVITY, 250, 350),
+    master(INSTANT_GRAVITY, 200, 300),
+    master(INSTANT_GRAVITY, 150, 250),
+    master(INSTANT_GRAVITY, 100, 200),
+    master(INSTANT_GRAVITY, 100, 170),
+];
@@ -1,125 +1,298 @@
-use crate::tetrimino::Tetrimino;
-use std::time::SystemTime;
+use crate::tetrimino::{Tetrimino, create_tetrimino};
+use crate::randomizer::{Randomizer, RandomizerKind};
+use crate::garbage::GARBAGE_CELL;
+use crate::scoring::{LockResult, Scoring, TSpin, detect_t_spin};
+use std::collections::VecDeque;
+use std::time::Duration;
 
-const LEVEL_TIMES: [u32; 10] = [1000, 850, 700, 600, 500, 400, 300, 250, 221, 190];
-const LEVEL_LINES: [u32; 10] = [20, 40, 60, 80, 100, 120, 140, 160, 180, 200];
+mod level_data;
+
+pub use self::level_data::{Level, LevelCurve, FRAME_MILLIS, INSTANT_GRAVITY, MARATHON_CURVE,
+                           MASTER_CURVE};
+
+pub const DEFAULT_WIDTH: usize = 10;
+pub const DEFAULT_HEIGHT: usize = 20;
+pub const DEFAULT_NEXT_COUNT: usize = 5;
+pub const MAX_NEXT_COUNT: usize = 7;
+// hidden rows above the visible playfield, pieces spawn in here
+pub const BUFFER_HEIGHT: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TopOut {
+    // a new piece overlaps the stack where it spawns
+    BlockOut,
+    // a piece locked entirely inside the hidden buffer
+    LockOut,
+    // incoming lines pushed blocks out of the top of the matrix
+    GarbageOut,
+}
+
+// What ends a game besides topping out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    None,
+    // clearing this many lines
+    Lines(u32),
+    // playing for this long
+    Time(Duration),
+    // clearing this many garbage rows
+    Garbage(u32),
+}
 
 pub struct Tetris {
+    // buffer rows followed by the visible ones
     pub game_map: Vec<Vec<u8>>,
+    // when each cell of `game_map` was filled, in game time
+    pub lock_times: Vec<Vec<Duration>>,
+    // game time, kept up to date by whoever drives the game
+    pub clock: Duration,
+    // game time of the last line clear
+    pub last_clear: Option<Duration>,
+    pub width: usize,
+    // visible rows only
+    pub height: usize,
+    // map cells per side of a mino, 2 in big mode
+    pub scale: usize,
     pub current_level: u32,
+    // when false the level, and so the gravity, never changes
+    pub level_up: bool,
+    pub curve: LevelCurve,
     pub score: u32,
     pub nb_lines: u32,
+    // cleared lines that had garbage in them
+    pub garbage_cleared: u32,
+    pub scoring: Scoring,
     pub current_piece: Option<Tetrimino>,
     pub holding_piece: Option<Tetrimino>,
-    pub next_piece: Tetrimino,
+    // hold can be used once per piece, until it locks
+    pub can_hold: bool,
+    // upcoming pieces, the front one spawns next
+    pub next_pieces: VecDeque<Tetrimino>,
+    pub top_out: Option<TopOut>,
+    pub goal: Goal,
+    randomizer: Box<dyn Randomizer>,
 }
 
 impl Tetris {
-    pub fn new() -> Tetris {
+    pub fn new(seed: u64) -> Tetris {
+        Tetris::with_randomizer(RandomizerKind::SevenBag.create(seed),
+                                DEFAULT_WIDTH, DEFAULT_HEIGHT, DEFAULT_NEXT_COUNT)
+    }
+
+    pub fn with_randomizer(mut randomizer: Box<dyn Randomizer>,
+                           width: usize, height: usize, next_count: usize) -> Tetris {
+        let next_pieces = (0..next_count.clamp(1, MAX_NEXT_COUNT))
+            .map(|_| create_tetrimino(randomizer.next_kind()))
+            .collect();
         Tetris {
-            game_map: vec![vec![0; 10]; 16],
+            game_map: vec![vec![0; width]; BUFFER_HEIGHT + height],
+            lock_times: vec![vec![Duration::from_millis(0); width]; BUFFER_HEIGHT + height],
+            clock: Duration::from_millis(0),
+            last_clear: None,
+            width,
+            height,
+            scale: 1,
             current_level: 1,
+            level_up: true,
+            curve: MARATHON_CURVE,
             score: 0,
             nb_lines: 0,
+            garbage_cleared: 0,
+            scoring: Scoring::new(),
             current_piece: None,
             holding_piece: None,
-            next_piece: crate::tetrimino::create_new_tetrimino(),
+            can_hold: true,
+            next_pieces,
+            top_out: None,
+            goal: Goal::None,
+            randomizer,
         }
     }
 
-    fn update_score(&mut self, to_add: u32) {
+    pub fn update_score(&mut self, to_add: u32) {
         self.score += to_add;
     }
 
-    fn check_lines(&mut self) {
-        let mut y = 0;
-        let mut score_add = 0;
-
-        while y < self.game_map.len() {
-            let mut complete = true;
-
-            for x in &self.game_map[y] {
-                if *x == 0 {
-                    complete = false;
-                    break;
-                }
-            }
-            if complete == true {
-                score_add += self.current_level;
-                self.game_map.remove(y);
-                y -= 1;
-            }
-            y += 1;
-        }
-        if self.game_map.len() == 0 {
-            // A "tetris"!
-            score_add += 1000;
+    // Removes complete lines and returns how many there were, in minos
+    fn check_lines(&mut self) -> u32 {
+        let height = self.game_map.len();
+        self.garbage_cleared += self.game_map.iter()
+            .filter(|line| !line.contains(&0) && line.contains(&GARBAGE_CELL))
+            .count() as u32;
+        let full: Vec<bool> = self.game_map.iter().map(|line| !line.contains(&0)).collect();
+        let mut rows = full.iter();
+        self.game_map.retain(|_| !rows.next().unwrap());
+        let mut rows = full.iter();
+        self.lock_times.retain(|_| !rows.next().unwrap());
+        // big minos clear rows two by two
+        let cleared = (height - self.game_map.len()) as u32 / self.scale as u32;
+        while self.game_map.len() < height {
+            self.game_map.insert(0, vec![0; self.width]);
+            self.lock_times.insert(0, vec![self.clock; self.width]);
         }
-        self.update_score(score_add);
-        while self.game_map.len() < 16 {
-            // we'll add this method just after!
+        for _ in 0..cleared {
             self.increase_line();
-            self.game_map.insert(0, vec![0, 0, 0, 0, 0, 0, 0, 0, 0,
-                                         0]);
         }
+        if cleared > 0 {
+            self.last_clear = Some(self.clock);
+        }
+        cleared
     }
 
-    pub fn make_permanent(&mut self) {
-        let mut to_add = 0;
-        if let Some(ref mut piece) = self.current_piece {
-            let mut shift_y = 0;
-
-            while shift_y < piece.states[piece.current_state as
-                usize].len() &&
-                piece.y + shift_y < self.game_map.len() {
-                let mut shift_x = 0;
-
-                while shift_x < piece.states[piece.current_state as usize]
-                    [shift_y].len() &&
-                    (piece.x + shift_x as isize) < self.game_map[piece.y
-                        + shift_y].len() as isize {
-                    if piece.states[piece.current_state as usize][shift_y]
-                        [shift_x] != 0 {
-                        let x = piece.x + shift_x as isize;
-                        self.game_map[piece.y + shift_y][x as usize] =
-                            piece.states[piece.current_state as usize]
-                                [shift_y][shift_x];
-                    }
-                    shift_x += 1;
+    pub fn make_permanent(&mut self) -> LockResult {
+        let mut t_spin = TSpin::None;
+        if let Some(ref piece) = self.current_piece {
+            t_spin = detect_t_spin(piece, &self.game_map);
+            let mut visible = false;
+            for (x, y, case) in piece.current_cells() {
+                if x < 0 || y < 0 ||
+                    y as usize >= self.game_map.len() ||
+                    x as usize >= self.game_map[y as usize].len() {
+                    continue;
                 }
-                shift_y += 1;
+                self.game_map[y as usize][x as usize] = case;
+                self.lock_times[y as usize][x as usize] = self.clock;
+                visible |= y as usize >= BUFFER_HEIGHT;
+            }
+            if !visible {
+                self.top_out = Some(TopOut::LockOut);
             }
-            to_add += self.current_level;
         }
-        self.update_score(to_add);
-        self.check_lines();
+        let level = self.current_level;
+        let lines = self.check_lines();
+        let result = LockResult {
+            lines,
+            t_spin,
+            perfect_clear: lines > 0 &&
+                self.game_map.iter().all(|line| line.iter().all(|case| *case == 0)),
+        };
+        let points = self.scoring.on_lock(result, level);
+        self.update_score(points);
         self.current_piece = None;
+        self.can_hold = true;
+        result
     }
 
     fn increase_line(&mut self) {
         self.nb_lines += 1;
-        if self.nb_lines > LEVEL_LINES[self.current_level as usize - 1] {
+        if self.level_up && self.nb_lines > self.level_end() {
             self.current_level += 1;
         }
     }
 
-    pub fn hold_piece(&mut self) {
-        if let Some(ref mut current_piece) = self.current_piece {
-            if let Some(ref mut holding_piece) = self.holding_piece {
-                holding_piece.change_position(&*self.game_map, current_piece.x, current_piece.y);
+    // Swaps the current piece with the held one, once per piece. Returns false if not allowed
+    pub fn hold_piece(&mut self) -> bool {
+        let kind = match self.current_piece {
+            Some(ref piece) if self.can_hold => piece.kind,
+            _ => return false,
+        };
+        self.can_hold = false;
+        self.current_piece = None;
+        // the held piece goes back to its spawn rotation
+        match self.holding_piece.replace(create_tetrimino(kind)) {
+            Some(piece) => self.spawn_piece(piece),
+            None => self.spawn_next_piece(),
+        };
+        true
+    }
+
+    // Moves the next piece into play, returns false on a block out
+    pub fn spawn_next_piece(&mut self) -> bool {
+        let piece = self.next_pieces[0].clone();
+        if self.spawn_piece(piece) {
+            self.next_pieces.pop_front();
+            self.next_pieces.push_back(create_tetrimino(self.randomizer.next_kind()));
+            true
+        } else {
+            false
+        }
+    }
+
+    fn spawn_piece(&mut self, mut piece: Tetrimino) -> bool {
+        // left-centered, like guideline pieces on a 10 wide playfield
+        piece.scale = self.scale;
+        piece.x = ((self.width / self.scale) as isize - 3) / 2;
+        // the flat side of a spawning piece sits on the row right above the visible playfield
+        piece.y = (BUFFER_HEIGHT / self.scale) as isize - 2;
+        if !piece.test_current_position(&self.game_map) {
+            self.top_out = Some(TopOut::BlockOut);
+            return false;
+        }
+        // drops into the visible playfield right away when nothing is in the way
+        if piece.test_position(&self.game_map, piece.current_state as usize,
+                               piece.x, piece.y + 1) {
+            piece.y += 1;
+        }
+        self.current_piece = Some(piece);
+        true
+    }
+
+    // Pushes lines in from the bottom of the matrix, lifting everything above them
+    pub fn push_lines(&mut self, lines: Vec<Vec<u8>>) {
+        for line in lines {
+            let top_line = self.game_map.remove(0);
+            self.lock_times.remove(0);
+            if top_line.iter().any(|case| *case != 0) {
+                self.top_out = Some(TopOut::GarbageOut);
+            }
+            self.game_map.push(line);
+            self.lock_times.push(vec![self.clock; self.width]);
+        }
+        if let Some(ref mut piece) = self.current_piece {
+            while !piece.test_current_position(&self.game_map) && piece.y > -4 {
+                piece.y -= 1;
             }
         }
-        std::mem::swap(&mut self.holding_piece, &mut self.current_piece);
     }
-}
 
-pub fn is_time_over(tetris: &Tetris, timer: &SystemTime) -> bool {
-    match timer.elapsed() {
-        Ok(elapsed) => {
-            let millis = elapsed.as_secs() as u32 * 1000 + elapsed.subsec_nanos() /
-                1_000_000;
-            millis > LEVEL_TIMES[tetris.current_level as usize - 1]
+    // Whether the goal is met after playing for `elapsed`
+    pub fn goal_reached(&self, elapsed: Duration) -> bool {
+        match self.goal {
+            Goal::None => false,
+            Goal::Lines(lines) => self.nb_lines >= lines,
+            Goal::Time(limit) => elapsed >= limit,
+            Goal::Garbage(lines) => self.garbage_cleared >= lines,
         }
-        Err(_) => false,
     }
-}
\ No newline at end of file
+
+    // Empties the whole matrix and forgets about a top out, the current piece is kept
+    pub fn clear_board(&mut self) {
+        for line in self.game_map.iter_mut() {
+            for case in line.iter_mut() {
+                *case = 0;
+            }
+        }
+        for line in self.lock_times.iter_mut() {
+            for time in line.iter_mut() {
+                *time = self.clock;
+            }
+        }
+        self.top_out = None;
+    }
+
+    // Rows with garbage in them still on the board
+    pub fn garbage_height(&self) -> usize {
+        self.game_map.iter().filter(|line| line.contains(&GARBAGE_CELL)).count()
+    }
+
+    // Speed of the current level, levels past the end of the curve stay at its last one
+    pub fn level(&self) -> &Level {
+        let index = (self.current_level as usize - 1).min(self.curve.len() - 1);
+        &self.curve[index]
+    }
+
+    // Total lines to clear before leaving the current level
+    fn level_end(&self) -> u32 {
+        (1..=self.current_level)
+            .map(|level| {
+                let index = (level as usize - 1).min(self.curve.len() - 1);
+                self.curve[index].lines
+            })
+            .sum()
+    }
+
+    // Time it takes gravity to move the piece down a row
+    pub fn gravity_delay(&self) -> Duration {
+        Duration::from_secs_f64(FRAME_MILLIS / self.level().gravity / 1000.)
+    }
+}